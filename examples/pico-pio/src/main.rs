@@ -32,7 +32,7 @@ use embedded_alloc::Heap;
 use seeed_lora_e5_at_commands::client::asynch::{JoinStatus, SeeedLoraE5Client};
 use seeed_lora_e5_at_commands::digester::LoraE5Digester;
 use seeed_lora_e5_at_commands::lora::types::{LoraClass, LoraJoinMode, LoraRegion};
-use seeed_lora_e5_at_commands::urc::URCMessages;
+use seeed_lora_e5_at_commands::urc::{LoraE5State, URCMessages};
 use static_cell::StaticCell;
 
 const APP_KEY: u128 = 0xd65b042878144e038a744359c7cd1f9d;
@@ -49,8 +49,10 @@ const URC_SUBSCRIBERS: usize = 0;
 // const URC_CAPACITY: usize = RX_SIZE * 1;
 const URC_CAPACITY: usize = 40;
 
+static LORA_STATE: LoraE5State = LoraE5State::new();
+
 type AtIngress<'a> =
-    Ingress<'a, LoraE5Digester, URCMessages, INGRESS_BUF_SIZE, URC_CAPACITY, URC_SUBSCRIBERS>;
+    Ingress<'a, LoraE5Digester<'a>, URCMessages, INGRESS_BUF_SIZE, URC_CAPACITY, URC_SUBSCRIBERS>;
 
 type AtLoraE5Client<'a> = Client<'a, PioUartTx<'a>, INGRESS_BUF_SIZE>;
 
@@ -80,7 +82,7 @@ async fn main(spawner: Spawner) {
         .cmd_cooldown(Duration::from_millis(200))
         .tx_timeout(Duration::from_millis(2000));
 
-    let digester = LoraE5Digester::default();
+    let digester = LoraE5Digester::new(&LORA_STATE);
 
     static RES_SLOT: ResponseSlot<INGRESS_BUF_SIZE> = ResponseSlot::new();
     static INGRESS_BUF: StaticCell<[u8; INGRESS_BUF_SIZE]> = StaticCell::new();
@@ -105,7 +107,7 @@ async fn read_task(mut ingress: AtIngress<'static>, mut rx: PioUartRx<'static>)
 
 #[embassy_executor::task]
 async fn client_task(client: AtLoraE5Client<'static>) {
-    let client = SeeedLoraE5Client::new(client).await;
+    let client = SeeedLoraE5Client::new(client, &LORA_STATE).await;
     if let Err(e) = client {
         error!("Error creating client {}", e);
         return;
@@ -196,10 +198,9 @@ async fn client_task(client: AtLoraE5Client<'static>) {
                 uplink_frame_count = uplink_frame_count_get;
             }
         }
-        let _ = client.confirm_send_set(true).await;
-        match client.send(1, 12, b"Hello from Lora-E5").await {
-            Ok(_d) => {
-                info!("Sent bytes");
+        match client.send_confirmed(12, b"Hello from Lora-E5", 1).await {
+            Ok(report) => {
+                info!("Sent bytes, ACK received: {}", report.ack_received);
             }
             Err(e) => error!("Error sending {}", e),
         }
@@ -1,77 +1,460 @@
+use embassy_time::Duration;
+use heapless::String;
+
+/// How long the module gets to answer the wake up bytes with `+LOWPOWER: WAKEUP`
+pub(crate) const WAKE_UP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// How long the module gets to boot and print its `+RESET` banner after a reset
+pub(crate) const BOOT_TIMEOUT: Duration = Duration::from_millis(5000);
+
+/// How long NRST is held low for a hard reset
+pub(crate) const RESET_PULSE_MS: u32 = 10;
+
+/// NRST line of the LoRa-E5, with the delay used to hold it low, for hard resetting the module
+pub struct ResetPin<P, D> {
+    pub(crate) pin: P,
+    pub(crate) delay: D,
+}
+
+impl<P, D> ResetPin<P, D> {
+    pub fn new(pin: P, delay: D) -> Self {
+        Self { pin, delay }
+    }
+}
+
+#[derive(Clone, Debug, Copy)]
+pub enum JoinStatus {
+    Joining,
+    Success,
+    Failure,
+    NotJoined,
+    Unknown,
+}
+
+pub struct OtaaJoinStatus {
+    pub join_status: JoinStatus,
+    pub net_id: Option<String<12>>,
+    pub dev_addr: Option<String<22>>,
+}
+
 #[cfg(feature = "async")]
 pub mod asynch {
+    pub use crate::client::{JoinStatus, OtaaJoinStatus, ResetPin};
+    use crate::client::{BOOT_TIMEOUT, RESET_PULSE_MS, WAKE_UP_TIMEOUT};
+    use crate::error::LoraE5Error;
+    use crate::general::commands::WakeUp;
+    #[cfg(feature = "debug")]
     use crate::general::responses::VerResponse;
+    use crate::lora::{types::LoraJoinMode, DEFAULT_RX2_DELAY_MS};
+    use crate::urc::{LoraE5State, DOWNLINK_QUEUE_SIZE};
+    use atat::asynch::AtatClient;
     pub use atat::asynch::Client;
-    use atat::Error;
+    use atat::{AtatCmd, Error};
     #[cfg(feature = "debug")]
     use defmt::{error, info, warn};
+    use embassy_time::with_timeout;
+    use embedded_hal::digital::OutputPin;
+    use embedded_hal_async::delay::DelayNs;
     pub use embedded_io_async::Write;
-    use heapless::String;
-
-    #[derive(Clone, Debug, Copy)]
-    pub enum JoinStatus {
-        Joining,
-        Success,
-        Failure,
-        NotJoined,
-        Unknown,
+
+    /// AT client that wakes the module up before sending a command when it is asleep
+    pub(crate) struct WakingClient<
+        'a,
+        W: Write,
+        const INGRESS_BUF_SIZE: usize,
+        const QUEUE_SIZE: usize,
+    > {
+        pub(crate) client: Client<'a, W, INGRESS_BUF_SIZE>,
+        pub(crate) state: &'a LoraE5State<QUEUE_SIZE>,
+        /// `AT+LOWPOWER=AUTOON` is active, the module sleeps after every command and has to be
+        /// woken before the next one
+        pub(crate) auto_low_power: bool,
     }
 
-    pub struct OtaaJoinStatus {
-        pub join_status: JoinStatus,
-        pub net_id: Option<String<12>>,
-        pub dev_addr: Option<String<22>>,
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize>
+        WakingClient<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>
+    {
+        /// Wake the module with 4x 0xFF and wait for `+LOWPOWER: WAKEUP`
+        pub(crate) async fn wake_up(&mut self) -> Result<(), Error> {
+            let state = self.state;
+            state.power_state_changed.reset();
+            self.client.send(&WakeUp {}).await?;
+            with_timeout(WAKE_UP_TIMEOUT, async {
+                loop {
+                    state.power_state_changed.wait().await;
+                    if !state.power_state().asleep {
+                        break;
+                    }
+                }
+            })
+            .await
+            .map_err(|_| Error::Timeout)
+        }
+
+        /// Wait for the module to fall asleep (`true`) or wake up (`false`)
+        pub(crate) async fn power_state_wait(&self, asleep: bool) -> Result<(), Error> {
+            let state = self.state;
+            with_timeout(WAKE_UP_TIMEOUT, async {
+                while state.power_state().asleep != asleep {
+                    state.power_state_changed.wait().await;
+                }
+            })
+            .await
+            .map_err(|_| Error::Timeout)
+        }
+
+        pub(crate) async fn send<Cmd: AtatCmd>(
+            &mut self,
+            cmd: &Cmd,
+        ) -> Result<Cmd::Response, Error> {
+            if self.auto_low_power || self.state.power_state().asleep {
+                self.wake_up().await?;
+            }
+            self.client.send(cmd).await
+        }
     }
 
-    pub struct SeeedLoraE5Client<'a, W: Write, const INGRESS_BUF_SIZE: usize> {
-        pub(crate) client: Client<'a, W, INGRESS_BUF_SIZE>,
+    pub struct SeeedLoraE5Client<
+        'a,
+        W: Write,
+        const INGRESS_BUF_SIZE: usize,
+        const QUEUE_SIZE: usize = DOWNLINK_QUEUE_SIZE,
+        R = (),
+    > {
+        pub(crate) client: WakingClient<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>,
+        pub(crate) state: &'a LoraE5State<QUEUE_SIZE>,
         pub(crate) join_status: OtaaJoinStatus,
+        pub(crate) confirmed_sending: bool,
+        /// Mode to go back to when the RF test stops
+        pub(crate) rf_test_restore_mode: Option<LoraJoinMode>,
+        /// RX2 delay as last set or read, bounds the wait for the end of an uplink
+        pub(crate) rx2_delay_ms: u16,
+        /// [max_tx_len](Self::max_tx_len) as last read, `None` once the data rate may have changed
+        pub(crate) max_tx_len_cached: Option<u8>,
+        /// NRST line for hard resets, `()` without one
+        pub(crate) reset_pin: R,
     }
 
-    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize> SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE> {
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
         pub fn eject_client(self) -> Client<'a, W, INGRESS_BUF_SIZE> {
-            self.client
+            self.client.client
+        }
+
+        pub fn state(&self) -> &'a LoraE5State<QUEUE_SIZE> {
+            self.state
+        }
+
+        fn build(
+            client: Client<'a, W, INGRESS_BUF_SIZE>,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+            reset_pin: R,
+        ) -> Self {
+            Self {
+                client: WakingClient {
+                    client,
+                    state,
+                    auto_low_power: false,
+                },
+                state,
+                join_status: OtaaJoinStatus {
+                    join_status: JoinStatus::NotJoined,
+                    net_id: None,
+                    dev_addr: None,
+                },
+                confirmed_sending: false,
+                rf_test_restore_mode: None,
+                rx2_delay_ms: DEFAULT_RX2_DELAY_MS,
+                max_tx_len_cached: None,
+                reset_pin,
+            }
+        }
+
+        /// Check the comms, factory resetting the module if it does not answer
+        async fn start(mut self) -> Result<Self, LoraE5Error> {
+            #[cfg(feature = "debug")]
+            if let Err(e) = self.verify_com_is_working().await {
+                error!("Error verifying Seeed LoRa-E5 comms: {:?}", e);
+            }
+
+            #[cfg(not(feature = "debug"))]
+            let _ = self.verify_com_is_working().await;
+            let mut count_down = 10;
+            while self.verify_com_is_working().await.is_err() && count_down > 0 {
+                #[cfg(feature = "debug")]
+                warn!("Waiting for LoRa-E5 to reset...");
+                count_down -= 1;
+            }
+            if count_down == 0 {
+                self.factory_reset().await?;
+                return Err(Error::Timeout.into());
+            }
+
+            #[cfg(feature = "debug")]
+            {
+                let version = self.version().await;
+                match version {
+                    Err(e) => {
+                        error!("Error getting Seeed LoRa-E5 firmware version: {:?}", e);
+                    }
+                    Ok(VerResponse {
+                        major,
+                        minor,
+                        patch,
+                    }) => {
+                        info!(
+                            "Seeed LoRa-E5 firmware version: {}.{}.{}",
+                            major, minor, patch
+                        );
+                    }
+                }
+            }
+
+            Ok(self)
+        }
+
+        /// Wait for the `+RESET` banner of a (re)booting module and forget the state it lost.
+        /// [LoraE5State::booted](crate::urc::LoraE5State) has to be reset before the reset.
+        pub(crate) async fn boot_wait(&mut self) -> Result<(), LoraE5Error> {
+            let state = self.state;
+            with_timeout(BOOT_TIMEOUT, state.booted.wait())
+                .await
+                .map_err(|_| Error::Timeout)?;
+            self.join_status = OtaaJoinStatus {
+                join_status: JoinStatus::NotJoined,
+                net_id: None,
+                dev_addr: None,
+            };
+            self.client.auto_low_power = false;
+            self.rf_test_restore_mode = None;
+            self.max_tx_len_cached = None;
+            Ok(())
         }
     }
 
-    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize> SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE> {
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>
+    {
         pub async fn new(
             client: Client<'a, W, INGRESS_BUF_SIZE>,
-        ) -> Result<SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE>, Error> {
-            let mut s = Self {
-                client,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+        ) -> Result<Self, LoraE5Error> {
+            Self::build(client, state, ()).start().await
+        }
+    }
+
+    impl<
+            'a,
+            W: Write,
+            const INGRESS_BUF_SIZE: usize,
+            const QUEUE_SIZE: usize,
+            P: OutputPin,
+            D: DelayNs,
+        > SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, ResetPin<P, D>>
+    {
+        /// Client that owns the NRST line of the module. The module is hard reset before starting,
+        /// recovering it if it was wedged
+        pub async fn new_with_reset_pin(
+            client: Client<'a, W, INGRESS_BUF_SIZE>,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+            reset_pin: ResetPin<P, D>,
+        ) -> Result<Self, LoraE5Error> {
+            let mut s = Self::build(client, state, reset_pin);
+            if let Err(_e) = s.hard_reset().await {
+                #[cfg(feature = "debug")]
+                error!("Error hard resetting Seeed LoRa-E5: {:?}", _e);
+            }
+            s.start().await
+        }
+
+        /// Hard reset the module by pulling NRST low, and wait for it to boot
+        pub async fn hard_reset(&mut self) -> Result<(), LoraE5Error> {
+            self.state.booted.reset();
+            let ResetPin { pin, delay } = &mut self.reset_pin;
+            pin.set_low().map_err(|_| LoraE5Error::ResetPin)?;
+            delay.delay_ms(RESET_PULSE_MS).await;
+            pin.set_high().map_err(|_| LoraE5Error::ResetPin)?;
+            self.boot_wait().await
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    pub use crate::client::{JoinStatus, OtaaJoinStatus, ResetPin};
+    use crate::client::{BOOT_TIMEOUT, RESET_PULSE_MS, WAKE_UP_TIMEOUT};
+    use crate::error::LoraE5Error;
+    use crate::general::commands::WakeUp;
+    #[cfg(feature = "debug")]
+    use crate::general::responses::VerResponse;
+    use crate::lora::{types::LoraJoinMode, DEFAULT_RX2_DELAY_MS};
+    use crate::urc::{LoraE5State, DOWNLINK_QUEUE_SIZE};
+    use atat::blocking::AtatClient;
+    pub use atat::blocking::Client;
+    use atat::{AtatCmd, Error};
+    #[cfg(feature = "debug")]
+    use defmt::{error, info, warn};
+    use embassy_time::{Duration, Instant};
+    use embedded_hal::delay::DelayNs;
+    use embedded_hal::digital::OutputPin;
+    pub use embedded_io::Write;
+
+    /// How long the blocking `receive` functions wait for a downlink or TEST mode packet,
+    /// call them again to keep waiting
+    pub const RECEIVE_TIMEOUT: Duration = Duration::from_millis(60000);
+
+    /// Poll until `poll` yields a value, giving up with [Timeout](LoraE5Error::Timeout)
+    /// after `timeout`
+    pub(crate) fn poll_until<T>(
+        timeout: Duration,
+        mut poll: impl FnMut() -> Option<T>,
+    ) -> Result<T, LoraE5Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(value) = poll() {
+                return Ok(value);
+            }
+            if Instant::now() > deadline {
+                return Err(LoraE5Error::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// AT client that wakes the module up before sending a command when it is asleep
+    pub(crate) struct WakingClient<
+        'a,
+        W: Write,
+        const INGRESS_BUF_SIZE: usize,
+        const QUEUE_SIZE: usize,
+    > {
+        pub(crate) client: Client<'a, W, INGRESS_BUF_SIZE>,
+        pub(crate) state: &'a LoraE5State<QUEUE_SIZE>,
+        /// `AT+LOWPOWER=AUTOON` is active, the module sleeps after every command and has to be
+        /// woken before the next one
+        pub(crate) auto_low_power: bool,
+    }
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize>
+        WakingClient<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>
+    {
+        /// Wake the module with 4x 0xFF and wait for `+LOWPOWER: WAKEUP`
+        pub(crate) fn wake_up(&mut self) -> Result<(), Error> {
+            self.state.power_state_changed.reset();
+            self.client.send(&WakeUp {})?;
+            let deadline = Instant::now() + WAKE_UP_TIMEOUT;
+            while self.state.power_state_changed.try_take().is_none()
+                || self.state.power_state().asleep
+            {
+                if Instant::now() > deadline {
+                    return Err(Error::Timeout);
+                }
+                core::hint::spin_loop();
+            }
+            Ok(())
+        }
+
+        /// Wait for the module to fall asleep (`true`) or wake up (`false`)
+        pub(crate) fn power_state_wait(&self, asleep: bool) -> Result<(), Error> {
+            let deadline = Instant::now() + WAKE_UP_TIMEOUT;
+            while self.state.power_state().asleep != asleep {
+                if Instant::now() > deadline {
+                    return Err(Error::Timeout);
+                }
+                core::hint::spin_loop();
+            }
+            Ok(())
+        }
+
+        pub(crate) fn send<Cmd: AtatCmd>(&mut self, cmd: &Cmd) -> Result<Cmd::Response, Error> {
+            if self.auto_low_power || self.state.power_state().asleep {
+                self.wake_up()?;
+            }
+            self.client.send(cmd)
+        }
+    }
+
+    pub struct SeeedLoraE5Client<
+        'a,
+        W: Write,
+        const INGRESS_BUF_SIZE: usize,
+        const QUEUE_SIZE: usize = DOWNLINK_QUEUE_SIZE,
+        R = (),
+    > {
+        pub(crate) client: WakingClient<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>,
+        pub(crate) state: &'a LoraE5State<QUEUE_SIZE>,
+        pub(crate) join_status: OtaaJoinStatus,
+        pub(crate) confirmed_sending: bool,
+        /// Mode to go back to when the RF test stops
+        pub(crate) rf_test_restore_mode: Option<LoraJoinMode>,
+        /// RX2 delay as last set or read, bounds the wait for the end of an uplink
+        pub(crate) rx2_delay_ms: u16,
+        /// [max_tx_len](Self::max_tx_len) as last read, `None` once the data rate may have changed
+        pub(crate) max_tx_len_cached: Option<u8>,
+        /// NRST line for hard resets, `()` without one
+        pub(crate) reset_pin: R,
+    }
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub fn eject_client(self) -> Client<'a, W, INGRESS_BUF_SIZE> {
+            self.client.client
+        }
+
+        pub fn state(&self) -> &'a LoraE5State<QUEUE_SIZE> {
+            self.state
+        }
+
+        fn build(
+            client: Client<'a, W, INGRESS_BUF_SIZE>,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+            reset_pin: R,
+        ) -> Self {
+            Self {
+                client: WakingClient {
+                    client,
+                    state,
+                    auto_low_power: false,
+                },
+                state,
                 join_status: OtaaJoinStatus {
                     join_status: JoinStatus::NotJoined,
                     net_id: None,
                     dev_addr: None,
                 },
-            };
+                confirmed_sending: false,
+                rf_test_restore_mode: None,
+                rx2_delay_ms: DEFAULT_RX2_DELAY_MS,
+                max_tx_len_cached: None,
+                reset_pin,
+            }
+        }
 
+        /// Check the comms, factory resetting the module if it does not answer
+        fn start(mut self) -> Result<Self, LoraE5Error> {
             #[cfg(feature = "debug")]
-            if let Err(e) = s.verify_com_is_working().await {
+            if let Err(e) = self.verify_com_is_working() {
                 error!("Error verifying Seeed LoRa-E5 comms: {:?}", e);
             }
 
             #[cfg(not(feature = "debug"))]
-            let _ = s.verify_com_is_working().await;
-            // if s.reset().await.is_err() {
-            //     #[cfg(feature = "debug")]
-            //     error!("Error resetting Seeed LoRa-E5");
-            // }
+            let _ = self.verify_com_is_working();
             let mut count_down = 10;
-            while s.verify_com_is_working().await.is_err() && count_down > 0 {
+            while self.verify_com_is_working().is_err() && count_down > 0 {
                 #[cfg(feature = "debug")]
                 warn!("Waiting for LoRa-E5 to reset...");
                 count_down -= 1;
             }
             if count_down == 0 {
-                s.factory_reset().await?;
-                return Err(Error::Timeout);
+                self.factory_reset()?;
+                return Err(Error::Timeout.into());
             }
 
             #[cfg(feature = "debug")]
             {
-                let version = s.version().await;
+                let version = self.version();
                 match version {
                     Err(e) => {
                         error!("Error getting Seeed LoRa-E5 firmware version: {:?}", e);
@@ -89,7 +472,74 @@ pub mod asynch {
                 }
             }
 
-            Ok(s)
+            Ok(self)
+        }
+
+        /// Wait for the `+RESET` banner of a (re)booting module and forget the state it lost.
+        /// [LoraE5State::booted](crate::urc::LoraE5State) has to be reset before the reset.
+        pub(crate) fn boot_wait(&mut self) -> Result<(), LoraE5Error> {
+            let deadline = Instant::now() + BOOT_TIMEOUT;
+            while self.state.booted.try_take().is_none() {
+                if Instant::now() > deadline {
+                    return Err(Error::Timeout.into());
+                }
+                core::hint::spin_loop();
+            }
+            self.join_status = OtaaJoinStatus {
+                join_status: JoinStatus::NotJoined,
+                net_id: None,
+                dev_addr: None,
+            };
+            self.client.auto_low_power = false;
+            self.rf_test_restore_mode = None;
+            self.max_tx_len_cached = None;
+            Ok(())
+        }
+    }
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE>
+    {
+        pub fn new(
+            client: Client<'a, W, INGRESS_BUF_SIZE>,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+        ) -> Result<Self, LoraE5Error> {
+            Self::build(client, state, ()).start()
+        }
+    }
+
+    impl<
+            'a,
+            W: Write,
+            const INGRESS_BUF_SIZE: usize,
+            const QUEUE_SIZE: usize,
+            P: OutputPin,
+            D: DelayNs,
+        > SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, ResetPin<P, D>>
+    {
+        /// Client that owns the NRST line of the module. The module is hard reset before starting,
+        /// recovering it if it was wedged
+        pub fn new_with_reset_pin(
+            client: Client<'a, W, INGRESS_BUF_SIZE>,
+            state: &'a LoraE5State<QUEUE_SIZE>,
+            reset_pin: ResetPin<P, D>,
+        ) -> Result<Self, LoraE5Error> {
+            let mut s = Self::build(client, state, reset_pin);
+            if let Err(_e) = s.hard_reset() {
+                #[cfg(feature = "debug")]
+                error!("Error hard resetting Seeed LoRa-E5: {:?}", _e);
+            }
+            s.start()
+        }
+
+        /// Hard reset the module by pulling NRST low, and wait for it to boot
+        pub fn hard_reset(&mut self) -> Result<(), LoraE5Error> {
+            self.state.booted.reset();
+            let ResetPin { pin, delay } = &mut self.reset_pin;
+            pin.set_low().map_err(|_| LoraE5Error::ResetPin)?;
+            delay.delay_ms(RESET_PULSE_MS);
+            pin.set_high().map_err(|_| LoraE5Error::ResetPin)?;
+            self.boot_wait()
         }
     }
 }
@@ -6,21 +6,30 @@ use atat::{
 };
 use atat::{
     nom,
-    nom::{branch, bytes, combinator, sequence},
-    DigestResult, Digester, Parser,
+    nom::{branch, bytes, character, combinator, sequence},
+    AtatUrc, DigestResult, Digester, Parser,
 };
 
 #[cfg(feature = "debug")]
 use crate::urc::LORA_LATEST_BUF;
 
-use crate::urc::URCMessages;
+use crate::urc::{LoraE5State, URCMessages, DOWNLINK_QUEUE_SIZE};
 #[cfg(feature = "debug")]
 use defmt::{debug, trace};
 
-#[derive(Default)]
-pub struct LoraE5Digester {}
+/// Digester for a single LoRa-E5 module.
+/// Every URC it matches is also applied to the module's [LoraE5State].
+pub struct LoraE5Digester<'a, const QUEUE_SIZE: usize = DOWNLINK_QUEUE_SIZE> {
+    state: &'a LoraE5State<QUEUE_SIZE>,
+}
+
+impl<'a, const QUEUE_SIZE: usize> LoraE5Digester<'a, QUEUE_SIZE> {
+    pub fn new(state: &'a LoraE5State<QUEUE_SIZE>) -> Self {
+        Self { state }
+    }
+}
 
-impl LoraE5Digester {
+impl<const QUEUE_SIZE: usize> LoraE5Digester<'_, QUEUE_SIZE> {
     pub fn custom_error(buf: &[u8]) -> Result<(&[u8], usize), ParseError> {
         let (_reminder, (head, data, tail)) = branch::alt((
             sequence::tuple((
@@ -135,12 +144,6 @@ impl LoraE5Digester {
                     bytes::streaming::tag("OK\r\n"),
                 )),
             )),
-            // +RESET / Startup preamble
-            sequence::tuple((
-                bytes::streaming::tag(b"+RESET: "),
-                bytes::streaming::take_until("\r\n\x00"),
-                bytes::streaming::tag("\r\n\x00"),
-            )),
             // +ID
             sequence::tuple((
                 combinator::recognize(sequence::tuple((
@@ -163,6 +166,16 @@ impl LoraE5Digester {
                 bytes::streaming::take_until("\r\n"),
                 bytes::streaming::tag("\r\n"),
             )),
+            // +DR query, `+DR: DR<n>` followed by the data rate details
+            sequence::tuple((
+                combinator::recognize(sequence::tuple((
+                    bytes::streaming::tag(b"+DR: DR"),
+                    character::streaming::digit1,
+                    bytes::streaming::tag(b"\r\n+DR: "),
+                ))),
+                bytes::streaming::take_until("\r\n"),
+                bytes::streaming::tag("\r\n"),
+            )),
             // +DR
             sequence::tuple((
                 bytes::streaming::tag(b"+DR: "),
@@ -250,6 +263,57 @@ impl LoraE5Digester {
                 ))),
                 bytes::streaming::tag("\r\n"),
             )),
+            // LoRaWAN settings and the remaining replies, nested as `alt` takes at most 21 parsers
+            branch::alt((
+                // +CH
+                sequence::tuple((
+                    bytes::streaming::tag(b"+CH: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +RXWIN1
+                sequence::tuple((
+                    bytes::streaming::tag(b"+RXWIN1: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +RXWIN2
+                sequence::tuple((
+                    bytes::streaming::tag(b"+RXWIN2: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +DELAY
+                sequence::tuple((
+                    bytes::streaming::tag(b"+DELAY: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +BEACON
+                sequence::tuple((
+                    bytes::streaming::tag(b"+BEACON: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +TEST
+                sequence::tuple((
+                    bytes::streaming::tag(b"+TEST: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +FDEFAULT
+                sequence::tuple((
+                    bytes::streaming::tag(b"+FDEFAULT: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+                // +LOWPOWER
+                sequence::tuple((
+                    bytes::streaming::tag(b"+LOWPOWER: "),
+                    bytes::streaming::take_until("\r\n"),
+                    bytes::streaming::tag("\r\n"),
+                )),
+            )),
         ))(buf)?;
         #[cfg(feature = "debug")]
         trace!("Custom success ! [{:?}]", LossyStr(data));
@@ -258,7 +322,7 @@ impl LoraE5Digester {
     }
 }
 
-impl Digester for LoraE5Digester {
+impl<const QUEUE_SIZE: usize> Digester for LoraE5Digester<'_, QUEUE_SIZE> {
     fn digest<'a>(&mut self, input: &'a [u8]) -> (DigestResult<'a>, usize) {
         #[cfg(feature = "debug")]
         let s = LossyStr(input);
@@ -273,6 +337,12 @@ impl Digester for LoraE5Digester {
             return (DigestResult::None, 4);
         }
 
+        // NUL bytes the module sends while it resets
+        let nuls = input.iter().take_while(|b| **b == 0).count();
+        if nuls > 0 {
+            return (DigestResult::None, nuls);
+        }
+
         // Generic success replies
         match parser::success_response(input) {
             Ok((_, (result, len))) => return (result, len),
@@ -282,22 +352,19 @@ impl Digester for LoraE5Digester {
 
         // 2. Match for URC's
         match <URCMessages as Parser>::parse(input) {
-            Ok((urc, len)) => return (DigestResult::Urc(urc), len),
-            Err(ParseError::Incomplete) => return incomplete,
-            _ => {}
-        }
-
-        // 3. Parse for success responses
-        // Custom successful replies first, if any
-        match (LoraE5Digester::custom_success)(input) {
-            Ok((response, len)) => return (DigestResult::Response(Ok(response)), len),
+            Ok((urc, len)) => {
+                if let Some(message) = <URCMessages as AtatUrc>::parse(urc) {
+                    self.state.handle_urc(&message);
+                }
+                return (DigestResult::Urc(urc), len);
+            }
             Err(ParseError::Incomplete) => return incomplete,
             _ => {}
         }
 
-        // 4. Parse for error responses
+        // 3. Parse for error responses
         // Custom error matches first, if any
-        match (LoraE5Digester::custom_error)(input) {
+        match (Self::custom_error)(input) {
             Ok((response, len)) => {
                 return (
                     DigestResult::Response(Err(InternalError::Custom(response))),
@@ -308,6 +375,14 @@ impl Digester for LoraE5Digester {
             _ => {}
         }
 
+        // 4. Parse for success responses
+        // Custom successful replies first, if any
+        match (Self::custom_success)(input) {
+            Ok((response, len)) => return (DigestResult::Response(Ok(response)), len),
+            Err(ParseError::Incomplete) => return incomplete,
+            _ => {}
+        }
+
         // Generic error matches
         if let Ok((_, (result, len))) = parser::error_response(input) {
             return (result, len);
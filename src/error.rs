@@ -0,0 +1,123 @@
+//! # Error type
+//!
+//! [LoraE5Error] maps the `ERROR(-n)` codes of the LoRa-E5 AT firmware and the LoRaWAN level
+//! failures reported by `MSG`/`CMSG`/`MSGHEX`/`CMSGHEX` to typed variants.
+
+use atat::Error;
+
+/// LoRa-E5 error
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(defmt::Format))]
+pub enum LoraE5Error {
+    /// ERROR(-1)
+    /// Parameter is invalid
+    InvalidParameter,
+    /// ERROR(-10)
+    /// Command unknown
+    UnknownCommand,
+    /// ERROR(-11)
+    /// Command is in wrong format
+    WrongFormat,
+    /// ERROR(-12)
+    /// Command is unavailable in current mode (check with `AT+MODE`)
+    UnavailableInCurrentMode,
+    /// ERROR(-20)
+    /// Too many parameters. LoRaWAN modem supports max 15 parameters
+    TooManyParameters,
+    /// ERROR(-21)
+    /// Length of command is too long (exceeds 528 bytes)
+    CommandTooLong,
+    /// ERROR(-22)
+    /// Receive end symbol timeout, command must end with <LF>
+    EndSymbolTimeout,
+    /// ERROR(-23)
+    /// Invalid character received
+    InvalidCharacter,
+    /// ERROR(-24)
+    /// Either -21, -22 or -23
+    CommandError,
+    /// Please join network first
+    NotJoined,
+    /// No free channel
+    NoFreeChannel,
+    /// Length error
+    /// Payload is too long for the current data rate
+    LengthError,
+    /// DR error
+    DataRateError,
+    /// LoRaWAN modem is busy
+    Busy,
+    /// The module echoed back a different value than was set
+    EchoMismatch,
+    /// Driving the NRST pin failed
+    ResetPin,
+    /// The network server did not answer a link check request
+    NoLinkCheckAnswer,
+    /// The module did not report the outcome of an exchange in time, e.g. a `Done` URC that
+    /// never came
+    Timeout,
+    /// Error from the AT client
+    At(Error),
+}
+
+impl LoraE5Error {
+    /// Error for an `ERROR(-n)` code
+    pub fn from_code(code: i8) -> Option<Self> {
+        let e = match code {
+            -1 => Self::InvalidParameter,
+            -10 => Self::UnknownCommand,
+            -11 => Self::WrongFormat,
+            -12 => Self::UnavailableInCurrentMode,
+            -20 => Self::TooManyParameters,
+            -21 => Self::CommandTooLong,
+            -22 => Self::EndSymbolTimeout,
+            -23 => Self::InvalidCharacter,
+            -24 => Self::CommandError,
+            _ => return None,
+        };
+        Some(e)
+    }
+
+    /// The `ERROR(-n)` code, if this error has one
+    pub fn code(&self) -> Option<i8> {
+        let code = match self {
+            Self::InvalidParameter => -1,
+            Self::UnknownCommand => -10,
+            Self::WrongFormat => -11,
+            Self::UnavailableInCurrentMode => -12,
+            Self::TooManyParameters => -20,
+            Self::CommandTooLong => -21,
+            Self::EndSymbolTimeout => -22,
+            Self::InvalidCharacter => -23,
+            Self::CommandError => -24,
+            _ => return None,
+        };
+        Some(code)
+    }
+
+    /// Parse the error part of a response, e.g. `ERROR(-12)` or `Please join network first`
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        match buf {
+            x if x.starts_with(b"ERROR(") => {
+                let code = x[6..].split(|b| *b == b')').next()?;
+                let code = core::str::from_utf8(code).ok()?.parse().ok()?;
+                Self::from_code(code)
+            }
+            x if x.starts_with(b"Please join network first") => Some(Self::NotJoined),
+            x if x.starts_with(b"No free channel") => Some(Self::NoFreeChannel),
+            x if x.starts_with(b"Length error") => Some(Self::LengthError),
+            x if x.starts_with(b"DR error") => Some(Self::DataRateError),
+            x if x.starts_with(b"LoRaWAN modem is busy") => Some(Self::Busy),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for LoraE5Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::CustomMessage(ref message) => Self::parse(message).unwrap_or(Self::At(value)),
+            e => Self::At(e),
+        }
+    }
+}
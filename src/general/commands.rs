@@ -24,20 +24,13 @@ impl AtatCmd for FirmwareVersion {
     const MAX_LEN: usize = 8;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"AT+VER\r\n");
+        buf[..8].copy_from_slice(b"AT+VER\r\n");
         8
     }
 
     fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
-        if resp.is_err() {
-            return Err(Error::Parse);
-        }
-        let buf = resp.unwrap();
-        let parse = Self::parse(buf).map_err(|_| Error::Parse);
-        if parse.is_err() {
-            return Err(Error::Parse);
-        }
-        let (major, minor, patch) = parse.unwrap();
+        let buf = resp.map_err(Error::from)?;
+        let (major, minor, patch) = Self::parse(buf).map_err(|_| Error::Parse)?;
 
         match (
             major.parse::<u8>(),
@@ -71,57 +64,136 @@ impl FirmwareVersion {
 }
 
 /// 4.4 RESET
-/// Reset the module
-#[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("+RESET", OkResponse, timeout_ms = 5000)]
+/// Reset the module. Its `+RESET: OK` banner is handled as a URC
+#[derive(Clone, Debug)]
 pub struct Reset {}
 
+impl AtatCmd for Reset {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 10;
+
+    const EXPECTS_RESPONSE_CODE: bool = false;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..10].copy_from_slice(b"AT+RESET\r\n");
+        10
+    }
+
+    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        Ok(NoResponse {})
+    }
+}
+
 /// 4.21 FDEFAULT
+/// Reset the configuration to the factory defaults
 #[derive(Clone, Debug)]
 pub struct FactoryReset {}
 
 impl AtatCmd for FactoryReset {
     type Response = OkResponse;
 
-    const MAX_LEN: usize = 20;
+    const MAX_LEN: usize = 19;
 
     const MAX_TIMEOUT_MS: u32 = 15000;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"+AT+FDEFAULT=Seeed\r\n");
-        20
+        buf[..19].copy_from_slice(b"AT+FDEFAULT=Seeed\r\n");
+        19
     }
 
-    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let buf = resp.map_err(Error::from)?;
+        let ok = core::str::from_utf8(buf).map_err(|_| Error::Parse)?.trim();
+        if ok != "OK" {
+            return Err(Error::Parse);
+        }
         Ok(OkResponse {
-            ok: String::from_str("OK").unwrap(),
+            ok: String::from_str(ok).map_err(|_| Error::Parse)?,
         })
     }
 }
 
 /// 4.30 LOWPOWER until woken up
-/// Sleep until woken by RX
-#[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("LOWPOWER", LowPowerResponse)]
+/// Sleep until woken by RX. The module confirms with a `+LOWPOWER: SLEEP` URC
+#[derive(Clone, Debug)]
 pub struct LowPowerUntilWokenUp {}
 
+impl AtatCmd for LowPowerUntilWokenUp {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 13;
+
+    const EXPECTS_RESPONSE_CODE: bool = false;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..13].copy_from_slice(b"AT+LOWPOWER\r\n");
+        13
+    }
+
+    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        Ok(NoResponse {})
+    }
+}
+
 /// 4.30 LOWPOWER for x milliseconds
-/// Sleep for x milliseconds
-#[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("LOWPOWER", LowPowerResponse)]
+/// Sleep for x milliseconds. The module confirms with a `+LOWPOWER: SLEEP` URC and sends
+/// `+LOWPOWER: WAKEUP` when it wakes up
+#[derive(Clone, Debug)]
 pub struct LowPowerForMilliseconds {
     pub sleep_for_millis: u32,
 }
 
+impl AtatCmd for LowPowerForMilliseconds {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 24;
+
+    const EXPECTS_RESPONSE_CODE: bool = false;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LOWPOWER={}\r\n", self.sleep_for_millis);
+        len - buf.len()
+    }
+
+    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        Ok(NoResponse {})
+    }
+}
+
 /// 4.30 LOWPOWER deep sleep enable
 /// Enter deep power saving mode
 #[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("LOWPOWER=AUTOON", NoResponse)]
+#[at_cmd("+LOWPOWER=AUTOON", LowPowerResponse)]
 pub struct LowPowerDeepSleepEnable {}
 
 /// 4.30 LOWPOWER deep sleep disable
 /// Stop deep power saving mode
 /// Needs 4x 0xFF over UART to be first sent
 #[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("LOWPOWER=AUTOOFF", NoResponse)]
+#[at_cmd("+LOWPOWER=AUTOOFF", LowPowerResponse)]
 pub struct LowPowerDeepSleepDisable {}
+
+/// 4.30 LOWPOWER wake up
+/// Four 0xFF bytes wake the module, which answers with a `+LOWPOWER: WAKEUP` URC
+#[derive(Clone, Debug)]
+pub struct WakeUp {}
+
+impl AtatCmd for WakeUp {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 4;
+
+    const EXPECTS_RESPONSE_CODE: bool = false;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..4].copy_from_slice(&[0xFF; 4]);
+        4
+    }
+
+    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        Ok(NoResponse {})
+    }
+}
@@ -1,61 +1,248 @@
 pub mod commands;
 pub mod responses;
 pub mod types;
+pub mod urc;
 
 #[cfg(feature = "async")]
 pub mod asynch {
     use crate::client::asynch::SeeedLoraE5Client;
-    use crate::general::commands::{FactoryReset, FirmwareVersion, Reset, VerifyComIsWorking};
+    use crate::error::LoraE5Error;
+    use crate::general::commands::{
+        FactoryReset, FirmwareVersion, LowPowerDeepSleepDisable, LowPowerDeepSleepEnable,
+        LowPowerForMilliseconds, LowPowerUntilWokenUp, Reset, VerifyComIsWorking,
+    };
     use crate::general::responses::VerResponse;
-    use atat::asynch::AtatClient;
-    use atat::Error;
+    use crate::general::types::PowerState;
+    use crate::lora::DEFAULT_RX2_DELAY_MS;
     #[cfg(feature = "debug")]
     use defmt::error;
     use embedded_io_async::Write;
 
-    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize> SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE> {
-        pub async fn verify_com_is_working(&mut self) -> Result<bool, Error> {
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub async fn verify_com_is_working(&mut self) -> Result<bool, LoraE5Error> {
             let command = VerifyComIsWorking {};
             let response = self.client.send(&command).await?;
             Ok(response.is_ok())
         }
 
-        pub async fn at_echo_on(&mut self) -> Result<bool, Error> {
+        pub async fn at_echo_on(&mut self) -> Result<bool, LoraE5Error> {
             // Nop
             Ok(true)
         }
 
-        pub async fn at_echo_set(&mut self, _on: bool) -> Result<bool, Error> {
+        pub async fn at_echo_set(&mut self, _on: bool) -> Result<bool, LoraE5Error> {
             // Nop
             Ok(true)
         }
 
-        pub async fn version(&mut self) -> Result<VerResponse, Error> {
+        pub async fn version(&mut self) -> Result<VerResponse, LoraE5Error> {
             let command = FirmwareVersion {};
             let response = self.client.send(&command).await?;
             Ok(response)
         }
 
-        pub async fn reset(&mut self) -> Result<(), Error> {
+        pub async fn reset(&mut self) -> Result<(), LoraE5Error> {
+            self.state.booted.reset();
             let command = Reset {};
-            let resp = self.client.send(&command).await;
-            if let Err(e) = resp {
-                #[cfg(feature = "debug")]
+            let resp = match self.client.send(&command).await {
+                Ok(_) => self.boot_wait().await,
+                Err(e) => Err(e.into()),
+            };
+            #[cfg(feature = "debug")]
+            if let Err(e) = &resp {
                 error!("Error resetting Seeed LoRa-E5: {:?}", e);
-                return Err(e);
             }
+            resp
+        }
+
+        pub async fn factory_reset(&mut self) -> Result<(), LoraE5Error> {
+            let command = FactoryReset {};
+            let resp = self.client.send(&command).await.map_err(LoraE5Error::from);
+            #[cfg(feature = "debug")]
+            if let Err(e) = &resp {
+                error!("Error factory resetting Seeed LoRa-E5: {:?}", e);
+            }
+            resp?;
+            self.rx2_delay_ms = DEFAULT_RX2_DELAY_MS;
+            self.max_tx_len_cached = None;
             Ok(())
         }
 
-        pub async fn factory_reset(&mut self) -> Result<(), Error> {
+        /// Sleep until woken up by [Self::wake_up] or the next command
+        pub async fn sleep_until_woken(&mut self) -> Result<(), LoraE5Error> {
+            self.client.send(&LowPowerUntilWokenUp {}).await?;
+            self.client.power_state_wait(true).await?;
+            Ok(())
+        }
+
+        /// Sleep for the given milliseconds. Commands sent before then wake the module first
+        pub async fn sleep_for(&mut self, ms: u32) -> Result<(), LoraE5Error> {
+            let command = LowPowerForMilliseconds {
+                sleep_for_millis: ms,
+            };
+            self.client.send(&command).await?;
+            self.client.power_state_wait(true).await?;
+            Ok(())
+        }
+
+        /// Wake the module up with 4x 0xFF and wait for `+LOWPOWER: WAKEUP`
+        pub async fn wake_up(&mut self) -> Result<(), LoraE5Error> {
+            self.client.wake_up().await?;
+            Ok(())
+        }
+
+        /// Whether the module is asleep, when it last fell asleep and woke up, and how long it
+        /// slept
+        pub fn power_state(&self) -> PowerState {
+            self.state.power_state()
+        }
+
+        /// Whether auto low power is on, in which case every command wakes the module first
+        pub fn auto_low_power(&self) -> bool {
+            self.client.auto_low_power
+        }
+
+        /// Turn auto low power on or off. While on, the module sleeps after every command
+        pub async fn auto_low_power_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            let expected = if on { "AUTOON" } else { "AUTOOFF" };
+            let response = if on {
+                self.client.send(&LowPowerDeepSleepEnable {}).await?
+            } else {
+                self.client.send(&LowPowerDeepSleepDisable {}).await?
+            };
+            if response.message.as_str() != expected {
+                return Err(LoraE5Error::EchoMismatch);
+            }
+            self.client.auto_low_power = on;
+            if on {
+                // Confirmed by `+LOWPOWER: AUTOON`, the module sleeps right after it
+                self.state.power_state_set(true);
+            }
+            Ok(on)
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    use crate::client::blocking::SeeedLoraE5Client;
+    use crate::error::LoraE5Error;
+    use crate::general::commands::{
+        FactoryReset, FirmwareVersion, LowPowerDeepSleepDisable, LowPowerDeepSleepEnable,
+        LowPowerForMilliseconds, LowPowerUntilWokenUp, Reset, VerifyComIsWorking,
+    };
+    use crate::general::responses::VerResponse;
+    use crate::general::types::PowerState;
+    use crate::lora::DEFAULT_RX2_DELAY_MS;
+    #[cfg(feature = "debug")]
+    use defmt::error;
+    use embedded_io::Write;
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub fn verify_com_is_working(&mut self) -> Result<bool, LoraE5Error> {
+            let command = VerifyComIsWorking {};
+            let response = self.client.send(&command)?;
+            Ok(response.is_ok())
+        }
+
+        pub fn at_echo_on(&mut self) -> Result<bool, LoraE5Error> {
+            // Nop
+            Ok(true)
+        }
+
+        pub fn at_echo_set(&mut self, _on: bool) -> Result<bool, LoraE5Error> {
+            // Nop
+            Ok(true)
+        }
+
+        pub fn version(&mut self) -> Result<VerResponse, LoraE5Error> {
+            let command = FirmwareVersion {};
+            let response = self.client.send(&command)?;
+            Ok(response)
+        }
+
+        pub fn reset(&mut self) -> Result<(), LoraE5Error> {
+            self.state.booted.reset();
+            let command = Reset {};
+            let resp = match self.client.send(&command) {
+                Ok(_) => self.boot_wait(),
+                Err(e) => Err(e.into()),
+            };
+            #[cfg(feature = "debug")]
+            if let Err(e) = &resp {
+                error!("Error resetting Seeed LoRa-E5: {:?}", e);
+            }
+            resp
+        }
+
+        pub fn factory_reset(&mut self) -> Result<(), LoraE5Error> {
             let command = FactoryReset {};
-            let resp = self.client.send(&command).await;
-            if let Err(e) = resp {
-                #[cfg(feature = "debug")]
+            let resp = self.client.send(&command).map_err(LoraE5Error::from);
+            #[cfg(feature = "debug")]
+            if let Err(e) = &resp {
                 error!("Error factory resetting Seeed LoRa-E5: {:?}", e);
-                return Err(e);
             }
+            resp?;
+            self.rx2_delay_ms = DEFAULT_RX2_DELAY_MS;
+            self.max_tx_len_cached = None;
             Ok(())
         }
+
+        /// Sleep until woken up by [Self::wake_up] or the next command
+        pub fn sleep_until_woken(&mut self) -> Result<(), LoraE5Error> {
+            self.client.send(&LowPowerUntilWokenUp {})?;
+            self.client.power_state_wait(true)?;
+            Ok(())
+        }
+
+        /// Sleep for the given milliseconds. Commands sent before then wake the module first
+        pub fn sleep_for(&mut self, ms: u32) -> Result<(), LoraE5Error> {
+            let command = LowPowerForMilliseconds {
+                sleep_for_millis: ms,
+            };
+            self.client.send(&command)?;
+            self.client.power_state_wait(true)?;
+            Ok(())
+        }
+
+        /// Wake the module up with 4x 0xFF and wait for `+LOWPOWER: WAKEUP`
+        pub fn wake_up(&mut self) -> Result<(), LoraE5Error> {
+            self.client.wake_up()?;
+            Ok(())
+        }
+
+        /// Whether the module is asleep, when it last fell asleep and woke up, and how long it
+        /// slept
+        pub fn power_state(&self) -> PowerState {
+            self.state.power_state()
+        }
+
+        /// Whether auto low power is on, in which case every command wakes the module first
+        pub fn auto_low_power(&self) -> bool {
+            self.client.auto_low_power
+        }
+
+        /// Turn auto low power on or off. While on, the module sleeps after every command
+        pub fn auto_low_power_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            let expected = if on { "AUTOON" } else { "AUTOOFF" };
+            let response = if on {
+                self.client.send(&LowPowerDeepSleepEnable {})?
+            } else {
+                self.client.send(&LowPowerDeepSleepDisable {})?
+            };
+            if response.message.as_str() != expected {
+                return Err(LoraE5Error::EchoMismatch);
+            }
+            self.client.auto_low_power = on;
+            if on {
+                // Confirmed by `+LOWPOWER: AUTOON`, the module sleeps right after it
+                self.state.power_state_set(true);
+            }
+            Ok(on)
+        }
     }
 }
@@ -1 +1,60 @@
+use embassy_time::{Duration, Instant};
 
+/// Power state of the module, tracked from its `+LOWPOWER` URCs.
+///
+/// While auto low power is on the module sleeps after every command without saying so; only
+/// the sleep confirmed by `+LOWPOWER: AUTOON` is recorded, the ones after later commands are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerState {
+    /// The module is sleeping
+    pub asleep: bool,
+    /// When the module last fell asleep
+    pub fell_asleep_at: Option<Instant>,
+    /// When the module last woke up
+    pub woke_at: Option<Instant>,
+    /// How long the last completed sleep lasted
+    pub last_sleep: Option<Duration>,
+    /// Time spent asleep over all completed sleeps
+    pub total_sleep: Duration,
+}
+
+impl Default for PowerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerState {
+    pub const fn new() -> Self {
+        Self {
+            asleep: false,
+            fell_asleep_at: None,
+            woke_at: None,
+            last_sleep: None,
+            total_sleep: Duration::from_ticks(0),
+        }
+    }
+
+    pub(crate) fn fell_asleep(&mut self, at: Instant) {
+        if self.asleep {
+            return;
+        }
+        self.asleep = true;
+        self.fell_asleep_at = Some(at);
+    }
+
+    pub(crate) fn woke_up(&mut self, at: Instant) {
+        if !self.asleep {
+            return;
+        }
+        self.asleep = false;
+        self.woke_at = Some(at);
+        if let Some(slept) = self
+            .fell_asleep_at
+            .and_then(|fell_asleep_at| at.checked_duration_since(fell_asleep_at))
+        {
+            self.last_sleep = Some(slept);
+            self.total_sleep += slept;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::urc::URCMessages;
+use atat::digest::ParseError;
+#[cfg(feature = "debug")]
+use atat::helpers::LossyStr;
+use atat::nom::{bytes, sequence};
+#[cfg(feature = "debug")]
+use defmt::trace;
+
+/// `+LOWPOWER` URCs, sent when the module falls asleep and when it wakes up
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LowPowerUrc {
+    /// `SLEEP`, after `AT+LOWPOWER` or `AT+LOWPOWER=<ms>`
+    Sleep,
+    /// `WAKEUP`, woken by 4x 0xFF or at the end of a timed sleep
+    WakeUp,
+}
+
+impl From<LowPowerUrc> for URCMessages {
+    fn from(value: LowPowerUrc) -> Self {
+        Self::LowPower(value)
+    }
+}
+
+impl LowPowerUrc {
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, ParseError> {
+        let (val, _) = sequence::tuple((bytes::streaming::tag("+LOWPOWER: "),))(buf)?;
+
+        #[cfg(feature = "debug")]
+        {
+            let v = LossyStr(val);
+            trace!("+LOWPOWER PARSE: {}", v);
+        }
+        match val {
+            x if x.starts_with(b"SLEEP") => Ok(LowPowerUrc::Sleep),
+            x if x.starts_with(b"WAKEUP") => Ok(LowPowerUrc::WakeUp),
+            _ => Err(ParseError::NoMatch),
+        }
+    }
+}
@@ -1,12 +1,15 @@
-#![feature(result_flattening)]
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 use atat_derive::AtatResp;
 
 pub mod client;
 pub mod digester;
+pub mod error;
 pub mod general;
 pub mod lora;
 pub mod signal;
+#[cfg(feature = "std")]
+pub mod simulator;
+pub mod test;
 pub mod urc;
 
 #[derive(Debug, Clone, AtatResp, PartialEq)]
@@ -1,25 +1,31 @@
 use super::responses::{
-    AbpDevAddrResponse, AdrGetSetResponse, AppKeySetResponse, DataRateGetSetResponse,
+    AbpDevAddrResponse, AdrGetSetResponse, BatteryLevelGetSetResponse, BeaconGetSetResponse,
+    BeaconStatusResponse, ChannelEnableSetResponse, ChannelNumSetResponse, ChannelSetResponse,
+    ChannelsGetResponse, CurrentDataRateResponse, DataRateGetSetResponse, KeySetResponse,
     LoRaWANClassGetSetResponse, LoraOtaaAutoJoinResponse, LoraOtaaJoinResponse,
-    MaxPayloadLengthGetResponse, ModeGetSetResponse, OtaaAppEuiResponse, OtaaDevEuiResponse,
-    PortGetSetResponse, RepeatGetSetResponse, RetryGetSetResponse, TxPowerForceSetResponse,
-    TxPowerTable, UplinkDownlinkCounterGetResponse,
+    LoraVersionGetSetResponse, LoraWanSwitchResponse, MaxPayloadLengthGetResponse,
+    ModeGetSetResponse, MulticastResponse, OtaaAppEuiResponse, OtaaDevEuiResponse,
+    PingSlotPeriodicityResponse, PortGetSetResponse, ReceiveDelaySetResponse,
+    ReceiveDelaysGetResponse, RepeatGetSetResponse, RetryGetSetResponse, Rx1ChannelSetResponse,
+    Rx1ChannelsGetResponse, Rx2WindowGetSetResponse, TxPowerForceSetResponse, TxPowerTable,
+    UplinkDownlinkCounterGetResponse,
+};
+use crate::lora::types::{
+    parse_receive_delay, BeaconChannel, BeaconStatus, DataRate, LoraChannel, LoraClass, LoraRegion,
+    LoraVersion, LoraWanSwitch, ReceiveDelay, ReceiveDelays, Rx1Channel, Rx2Window, MAX_UPLINK_LEN,
 };
-use crate::lora::types::{LoraClass, LoraRegion};
 use crate::NoResponse;
-use atat::{AtatCmd, AtatLen, Error, InternalError};
-use atat_derive::{AtatCmd, AtatLen};
+use atat::{AtatCmd, Error, InternalError};
+use atat_derive::AtatCmd;
 use core::str::FromStr;
 use heapless::String;
-use serde_at::{HexStr, SerializeOptions};
+use serde_at::HexStr;
 
 /// 4.3 ABP DevAddr Get
 /// Get the ABP mode DevAddr
 #[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("+ID", AbpDevAddrResponse)]
-pub struct AbpDevAddrGet {
-    pub dev_addr_text: String<14>,
-}
+#[at_cmd("+ID=DevAddr", AbpDevAddrResponse)]
+pub struct AbpDevAddrGet {}
 
 /// 4.3 ABP DevAddr Set
 /// Set the ABP DevAddr
@@ -30,6 +36,23 @@ pub struct AbpDevAddSet {
     pub dev_addr: HexStr<u32>,
 }
 
+impl AbpDevAddSet {
+    pub fn dev_addr(dev_addr: u32) -> Self {
+        let dev_addr = HexStr {
+            val: dev_addr,
+            add_0x_with_encoding: false,
+            hex_in_caps: true,
+            delimiter_after_nibble_count: 2,
+            delimiter: ' ',
+            skip_last_0_values: false,
+        };
+        Self {
+            dev_addr_text: String::from_str("DevAddr").unwrap(),
+            dev_addr,
+        }
+    }
+}
+
 /// 4.3 OTAA DevEUI Get
 /// Get the OTAA DevEUI
 #[derive(Clone, Debug, AtatCmd)]
@@ -105,10 +128,26 @@ pub struct MessageStringUnconfirmed {
 /// 4.5.1 Link check
 /// Send an empty string message in order to get the
 /// link status.
-#[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("+MSG", NoResponse)]
+#[derive(Clone, Debug)]
 pub struct LinkCheck {}
 
+impl AtatCmd for LinkCheck {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 8;
+
+    const EXPECTS_RESPONSE_CODE: bool = false;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..8].copy_from_slice(b"AT+MSG\r\n");
+        8
+    }
+
+    fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        Ok(NoResponse {})
+    }
+}
+
 /// 4.6 CMSG
 /// Send a string that needs to be confirmed by the server
 #[derive(Clone, Debug, AtatCmd)]
@@ -117,34 +156,36 @@ pub struct MessageStringConfirmed {
     pub message: String<128>,
 }
 
+/// Write `AT+<command>="<hex data>"`. The client rejects data longer than [MAX_UPLINK_LEN]
+/// with [LengthError](crate::error::LoraE5Error::LengthError) before building the command.
+fn write_hex_message(mut buf: &mut [u8], command: &str, data: &[u8]) -> usize {
+    use embedded_io::Write;
+    let len = buf.len();
+    let _ = write!(buf, "AT+{}=\"", command);
+    for byte in data {
+        let _ = write!(buf, "{:02X}", byte);
+    }
+    let _ = write!(buf, "\"\r\n");
+    len - buf.len()
+}
+
 /// 4.7 MSGHEX
-/// Send hex format data frame that doesn't need to be confirmed by the server
-#[derive(Clone, Debug, AtatLen)]
-pub struct MessageHexUnconfirmed {
-    pub message: HexStr<[u8; 242]>,
+/// Send hex format data frame that doesn't need to be confirmed by the server.
+/// Exactly `data.len()` bytes are sent, which must be at most [MAX_UPLINK_LEN].
+#[derive(Clone, Debug)]
+pub struct MessageHexUnconfirmed<'a> {
+    pub data: &'a [u8],
 }
 
-impl AtatCmd for MessageHexUnconfirmed {
+impl AtatCmd for MessageHexUnconfirmed<'_> {
     type Response = NoResponse;
-    const MAX_LEN: usize = MessageHexUnconfirmed::LEN + 20;
+
+    const MAX_LEN: usize = 14 + MAX_UPLINK_LEN * 2;
 
     const EXPECTS_RESPONSE_CODE: bool = false;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        let _ = buf.copy_from_slice(b"AT+MSGHEX=");
-        let hex_str = serde_at::to_string::<HexStr<[u8; 242]>, { MessageHexUnconfirmed::LEN }>(
-            &self.message,
-            "",
-            SerializeOptions::default(),
-        )
-        .expect("Failed to serialize message");
-        let len = hex_str.len();
-        let buf = &mut buf[10..];
-        buf[..len].copy_from_slice(hex_str.as_bytes());
-        let end = len + 10;
-        let buf = &mut buf[..end];
-        buf.copy_from_slice(b"\r\n");
-        end + 2
+        write_hex_message(buf, "MSGHEX", self.data)
     }
 
     fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
@@ -159,34 +200,22 @@ impl AtatCmd for MessageHexUnconfirmed {
 pub struct MessageHexUnconfirmedEmpty {}
 
 /// 4.8 CMSGHEX
-/// Send hex format data that needs to be confirmed by the server
-#[derive(Clone, Debug, AtatLen)]
-pub struct MessageHexConfirmed {
-    pub message: HexStr<[u8; 242]>,
+/// Send hex format data that needs to be confirmed by the server.
+/// Exactly `data.len()` bytes are sent, which must be at most [MAX_UPLINK_LEN].
+#[derive(Clone, Debug)]
+pub struct MessageHexConfirmed<'a> {
+    pub data: &'a [u8],
 }
 
-impl AtatCmd for MessageHexConfirmed {
+impl AtatCmd for MessageHexConfirmed<'_> {
     type Response = NoResponse;
-    const MAX_LEN: usize = MessageHexConfirmed::LEN + 22;
+
+    const MAX_LEN: usize = 15 + MAX_UPLINK_LEN * 2;
 
     const EXPECTS_RESPONSE_CODE: bool = false;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"AT+CMSGHEX=");
-        let buf = &mut buf[11..];
-        let hex_str = serde_at::to_string::<HexStr<[u8; 242]>, { MessageHexConfirmed::LEN }>(
-            &self.message,
-            "",
-            SerializeOptions::default(),
-        )
-        .expect("Failed to serialize message");
-        let len = hex_str.len();
-        let buf = &mut buf[11..];
-        buf[..len].copy_from_slice(hex_str.as_bytes());
-        let end = len + 11;
-        let buf = &mut buf[..end];
-        buf.copy_from_slice(b"\r\n");
-        end + 2
+        write_hex_message(buf, "CMSGHEX", self.data)
     }
 
     fn parse(&self, _resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
@@ -325,6 +354,459 @@ impl DataRateSchemeSet {
     }
 }
 
+/// 4.12 CH get
+/// List the channels of the channel plan
+#[derive(Clone, Debug)]
+pub struct ChannelsGet {}
+
+impl AtatCmd for ChannelsGet {
+    type Response = ChannelsGetResponse;
+
+    const MAX_LEN: usize = 7;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..7].copy_from_slice(b"AT+CH\r\n");
+        7
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let mut channels = heapless::Vec::new();
+        // The first part is the number of channels
+        for channel in resp.split(';').skip(1) {
+            let channel = LoraChannel::from_str(channel.trim()).map_err(|_| Error::Parse)?;
+            channels.push(channel).map_err(|_| Error::Parse)?;
+        }
+        Ok(ChannelsGetResponse { channels })
+    }
+}
+
+/// 4.12 CH set
+/// Set the frequency and data rate range of a channel, a frequency of 0 removes it
+#[derive(Clone, Debug)]
+pub struct ChannelSet {
+    pub index: u8,
+    pub frequency_hz: u32,
+    pub dr_min: u8,
+    pub dr_max: u8,
+}
+
+impl AtatCmd for ChannelSet {
+    type Response = ChannelSetResponse;
+
+    const MAX_LEN: usize = 32;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(
+            buf,
+            "AT+CH={},{}.{:06},{},{}\r\n",
+            self.index,
+            self.frequency_hz / 1_000_000,
+            self.frequency_hz % 1_000_000,
+            self.dr_min,
+            self.dr_max
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let channel = LoraChannel::from_str(resp).map_err(|_| Error::Parse)?;
+        Ok(ChannelSetResponse { channel })
+    }
+}
+
+/// 4.12 CH on/off
+/// Enable or disable a channel
+#[derive(Clone, Debug)]
+pub struct ChannelEnableSet {
+    pub index: u8,
+    pub on: bool,
+}
+
+impl AtatCmd for ChannelEnableSet {
+    type Response = ChannelEnableSetResponse;
+
+    const MAX_LEN: usize = 15;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let on = if self.on { "ON" } else { "OFF" };
+        let _ = write!(buf, "AT+CH={},{}\r\n", self.index, on);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let (index, on) = resp
+            .strip_prefix("CH")
+            .and_then(|r| r.split_once(' '))
+            .ok_or(Error::Parse)?;
+        let on = match on.trim() {
+            x if x.eq_ignore_ascii_case("ON") => true,
+            x if x.eq_ignore_ascii_case("OFF") => false,
+            _ => return Err(Error::Parse),
+        };
+        Ok(ChannelEnableSetResponse {
+            index: index.parse().map_err(|_| Error::Parse)?,
+            on,
+        })
+    }
+}
+
+/// 4.12 CH NUM
+/// Only enable the channels `first` to `last` (US915, AU915 and CN470 sub-band selection)
+#[derive(Clone, Debug)]
+pub struct ChannelNumSet {
+    pub first: u8,
+    pub last: u8,
+}
+
+impl AtatCmd for ChannelNumSet {
+    type Response = ChannelNumSetResponse;
+
+    const MAX_LEN: usize = 20;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+CH=NUM,{}-{}\r\n", self.first, self.last);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let range = resp.strip_prefix("NUM,").ok_or(Error::Parse)?.trim();
+        let (first, last) = range.split_once('-').ok_or(Error::Parse)?;
+        Ok(ChannelNumSetResponse {
+            first: first.parse().map_err(|_| Error::Parse)?,
+            last: last.parse().map_err(|_| Error::Parse)?,
+        })
+    }
+}
+
+/// 4.14 RXWIN1 get
+/// Get the custom RX1 channel frequencies
+#[derive(Clone, Debug)]
+pub struct Rx1ChannelsGet {}
+
+impl AtatCmd for Rx1ChannelsGet {
+    type Response = Rx1ChannelsGetResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+RXWIN1\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let mut channels = heapless::Vec::new();
+        // Skip the ON/OFF state and channel count
+        for channel in resp.split(';').filter(|part| part.contains(',')) {
+            let channel = Rx1Channel::from_str(channel.trim()).map_err(|_| Error::Parse)?;
+            channels.push(channel).map_err(|_| Error::Parse)?;
+        }
+        Ok(Rx1ChannelsGetResponse { channels })
+    }
+}
+
+/// 4.14 RXWIN1 set
+/// Set the frequency of RX1 channel `index`
+#[derive(Clone, Debug)]
+pub struct Rx1ChannelSet {
+    pub index: u8,
+    pub frequency_hz: u32,
+}
+
+impl AtatCmd for Rx1ChannelSet {
+    type Response = Rx1ChannelSetResponse;
+
+    const MAX_LEN: usize = 28;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(
+            buf,
+            "AT+RXWIN1={},{}.{:06}\r\n",
+            self.index,
+            self.frequency_hz / 1_000_000,
+            self.frequency_hz % 1_000_000
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let channel = Rx1Channel::from_str(resp).map_err(|_| Error::Parse)?;
+        Ok(Rx1ChannelSetResponse { channel })
+    }
+}
+
+/// 4.13 RXWIN2 get
+/// Get the RX2 window frequency and data rate
+#[derive(Clone, Debug)]
+pub struct Rx2WindowGet {}
+
+impl AtatCmd for Rx2WindowGet {
+    type Response = Rx2WindowGetSetResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+RXWIN2\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_rx2_window(resp)
+    }
+}
+
+/// 4.13 RXWIN2 set
+/// Set the RX2 window frequency and data rate
+#[derive(Clone, Debug)]
+pub struct Rx2WindowSet {
+    pub frequency_hz: u32,
+    pub data_rate: DataRate,
+}
+
+impl AtatCmd for Rx2WindowSet {
+    type Response = Rx2WindowGetSetResponse;
+
+    const MAX_LEN: usize = 30;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(
+            buf,
+            "AT+RXWIN2={}.{:06},DR{}\r\n",
+            self.frequency_hz / 1_000_000,
+            self.frequency_hz % 1_000_000,
+            self.data_rate.value()
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_rx2_window(resp)
+    }
+}
+
+fn parse_rx2_window(resp: Result<&[u8], InternalError>) -> Result<Rx2WindowGetSetResponse, Error> {
+    let resp = resp.map_err(Error::from)?;
+    let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+    let window = Rx2Window::from_str(resp).map_err(|_| Error::Parse)?;
+    Ok(Rx2WindowGetSetResponse { window })
+}
+
+/// 4.27 BEACON get
+/// Get the Class B beacon frequency and data rate
+#[derive(Clone, Debug)]
+pub struct BeaconGet {}
+
+impl AtatCmd for BeaconGet {
+    type Response = BeaconGetSetResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+BEACON\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_beacon(resp)
+    }
+}
+
+/// 4.27 BEACON set
+/// Set the Class B beacon frequency and data rate
+#[derive(Clone, Debug)]
+pub struct BeaconSet {
+    pub frequency_hz: u32,
+    pub data_rate: DataRate,
+}
+
+impl AtatCmd for BeaconSet {
+    type Response = BeaconGetSetResponse;
+
+    const MAX_LEN: usize = 30;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(
+            buf,
+            "AT+BEACON={}.{:06},DR{}\r\n",
+            self.frequency_hz / 1_000_000,
+            self.frequency_hz % 1_000_000,
+            self.data_rate.value()
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_beacon(resp)
+    }
+}
+
+fn parse_beacon(resp: Result<&[u8], InternalError>) -> Result<BeaconGetSetResponse, Error> {
+    let resp = resp.map_err(Error::from)?;
+    let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+    let channel = BeaconChannel::from_str(resp).map_err(|_| Error::Parse)?;
+    Ok(BeaconGetSetResponse { channel })
+}
+
+/// The value of a `+BEACON: <name>, <value>` response
+fn beacon_value<'a>(resp: Result<&'a [u8], InternalError>, name: &str) -> Result<&'a str, Error> {
+    let resp = resp.map_err(Error::from)?;
+    let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+    let (field, value) = resp.split_once(',').ok_or(Error::Parse)?;
+    if field.trim() != name {
+        return Err(Error::Parse);
+    }
+    Ok(value.trim())
+}
+
+/// 4.27 BEACON STATUS
+/// Get the Class B beacon acquisition state
+#[derive(Clone, Debug)]
+pub struct BeaconStatusGet {}
+
+impl AtatCmd for BeaconStatusGet {
+    type Response = BeaconStatusResponse;
+
+    const MAX_LEN: usize = 18;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..18].copy_from_slice(b"AT+BEACON=STATUS\r\n");
+        18
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let status =
+            BeaconStatus::from_str(beacon_value(resp, "STATUS")?).map_err(|_| Error::Parse)?;
+        Ok(BeaconStatusResponse { status })
+    }
+}
+
+/// 4.27 BEACON PERIOD get
+/// Get the ping slot periodicity, ping slots open every `2^periodicity` seconds
+#[derive(Clone, Debug)]
+pub struct PingSlotPeriodicityGet {}
+
+impl AtatCmd for PingSlotPeriodicityGet {
+    type Response = PingSlotPeriodicityResponse;
+
+    const MAX_LEN: usize = 18;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..18].copy_from_slice(b"AT+BEACON=PERIOD\r\n");
+        18
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_ping_slot_periodicity(resp)
+    }
+}
+
+/// 4.27 BEACON PERIOD set
+/// Set the ping slot periodicity (0 to 7), ping slots open every `2^periodicity` seconds
+#[derive(Clone, Debug)]
+pub struct PingSlotPeriodicitySet {
+    pub periodicity: u8,
+}
+
+impl AtatCmd for PingSlotPeriodicitySet {
+    type Response = PingSlotPeriodicityResponse;
+
+    const MAX_LEN: usize = 22;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+BEACON=PERIOD,{}\r\n", self.periodicity);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_ping_slot_periodicity(resp)
+    }
+}
+
+fn parse_ping_slot_periodicity(
+    resp: Result<&[u8], InternalError>,
+) -> Result<PingSlotPeriodicityResponse, Error> {
+    let periodicity = beacon_value(resp, "PERIOD")?
+        .parse()
+        .map_err(|_| Error::Parse)?;
+    Ok(PingSlotPeriodicityResponse { periodicity })
+}
+
+/// 4.16 DELAY get
+/// Get the RX1, RX2, join RX1 and join RX2 window delays
+#[derive(Clone, Debug)]
+pub struct ReceiveDelaysGet {}
+
+impl AtatCmd for ReceiveDelaysGet {
+    type Response = ReceiveDelaysGetResponse;
+
+    const MAX_LEN: usize = 10;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..10].copy_from_slice(b"AT+DELAY\r\n");
+        10
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let delays = ReceiveDelays::from_str(resp).map_err(|_| Error::Parse)?;
+        Ok(ReceiveDelaysGetResponse { delays })
+    }
+}
+
+/// 4.16 DELAY set
+/// Set one of the window delays in milliseconds
+#[derive(Clone, Debug)]
+pub struct ReceiveDelaySet {
+    pub delay: ReceiveDelay,
+    pub ms: u16,
+}
+
+impl AtatCmd for ReceiveDelaySet {
+    type Response = ReceiveDelaySetResponse;
+
+    const MAX_LEN: usize = 23;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+DELAY={},{}\r\n", self.delay.as_str(), self.ms);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let resp = resp.map_err(Error::from)?;
+        let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+        let (delay, ms) = parse_receive_delay(resp).map_err(|_| Error::Parse)?;
+        Ok(ReceiveDelaySetResponse { delay, ms })
+    }
+}
+
 /// 4.15.2 POWER force set
 /// Force set the dBm TX power
 #[derive(Clone, Debug)]
@@ -412,25 +894,69 @@ pub struct RetrySet {
 /// 4.20 KEY App key set
 /// Set the AppKey for OTAA
 #[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("+KEY", AppKeySetResponse)]
+#[at_cmd("+KEY", KeySetResponse)]
 pub struct AppKeySet {
     pub app_key_text: String<82>,
-    pub key: HexStr<[u8; 16]>,
+    pub key: HexStr<u128>,
 }
 
 impl AppKeySet {
     pub fn app_key(app_key: u128) -> Self {
-        let key = HexStr::<_> {
-            val: app_key.to_le_bytes(),
-            ..Default::default()
-        };
         Self {
             app_key_text: "APPKEY".try_into().unwrap(),
-            key,
+            key: key_hex(app_key),
+        }
+    }
+}
+
+/// 4.20 KEY NwkSKey set
+/// Set the network session key for ABP
+#[derive(Clone, Debug, AtatCmd)]
+#[at_cmd("+KEY", KeySetResponse)]
+pub struct NwkSKeySet {
+    pub nwk_s_key_text: String<12>,
+    pub key: HexStr<u128>,
+}
+
+impl NwkSKeySet {
+    pub fn nwk_s_key(nwk_s_key: u128) -> Self {
+        Self {
+            nwk_s_key_text: "NWKSKEY".try_into().unwrap(),
+            key: key_hex(nwk_s_key),
+        }
+    }
+}
+
+/// 4.20 KEY AppSKey set
+/// Set the application session key for ABP
+#[derive(Clone, Debug, AtatCmd)]
+#[at_cmd("+KEY", KeySetResponse)]
+pub struct AppSKeySet {
+    pub app_s_key_text: String<12>,
+    pub key: HexStr<u128>,
+}
+
+impl AppSKeySet {
+    pub fn app_s_key(app_s_key: u128) -> Self {
+        Self {
+            app_s_key_text: "APPSKEY".try_into().unwrap(),
+            key: key_hex(app_s_key),
         }
     }
 }
 
+/// 128 bit key as 32 hex characters
+fn key_hex(key: u128) -> HexStr<u128> {
+    HexStr {
+        val: key,
+        add_0x_with_encoding: false,
+        hex_in_caps: true,
+        delimiter_after_nibble_count: 0,
+        delimiter: ' ',
+        skip_last_0_values: false,
+    }
+}
+
 /// 4.23 MODE Get
 /// Get the mode (Test, OTAA or ABP)
 #[derive(Clone, Debug, AtatCmd)]
@@ -482,7 +1008,7 @@ impl AtatCmd for LoraJoinOtaa {
     const MAX_TIMEOUT_MS: u32 = 10000;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"AT+JOIN\r\n");
+        buf[..9].copy_from_slice(b"AT+JOIN\r\n");
         9
     }
 
@@ -491,11 +1017,10 @@ impl AtatCmd for LoraJoinOtaa {
             Ok(resp) => {
                 let response = core::str::from_utf8(resp)
                     .map_err(|_| Error::Parse)
-                    .map(|s| s.try_into().map_err(|_| Error::Parse))
-                    .flatten()?;
+                    .and_then(|s| s.try_into().map_err(|_| Error::Parse))?;
                 Ok(Self::Response { response })
             }
-            Err(_err) => Err(Error::Parse),
+            Err(err) => Err(err.into()),
         }
     }
 }
@@ -525,16 +1050,15 @@ impl AtatCmd for LoraAutoJoinOtaaDisable {
     const MAX_LEN: usize = 11;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"AT+JOIN=0\r\n");
+        buf[..11].copy_from_slice(b"AT+JOIN=0\r\n");
         11
     }
 
     fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
-        let buf = resp.map_err(|_| Error::Parse)?;
+        let buf = resp.map_err(Error::from)?;
         let resp = core::str::from_utf8(buf)
             .map_err(|_| Error::Parse)
-            .map(|b| b.try_into().map_err(|_| Error::Parse))
-            .flatten()?;
+            .and_then(|b| b.try_into().map_err(|_| Error::Parse))?;
         Ok(Self::Response { response: resp })
     }
 }
@@ -637,36 +1161,93 @@ impl AtatCmd for LoraUplinkDownlinkCounterGet {
     const MAX_LEN: usize = 12;
 
     fn write(&self, buf: &mut [u8]) -> usize {
-        buf.copy_from_slice(b"AT+LW=ULDL\r\n");
+        buf[..12].copy_from_slice(b"AT+LW=ULDL\r\n");
         12
     }
 
     fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
-        match resp {
-            Ok(resp) => {
-                let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
-                let mut resp = resp.split(',');
-                let uplink = resp.nth(1).ok_or(Error::Parse)?;
-                let downlink = resp.next().ok_or(Error::Parse)?;
-                Ok(Self::Response {
-                    uplink: uplink.parse().map_err(|_| Error::Parse)?,
-                    downlink: downlink.parse().map_err(|_| Error::Parse)?,
-                })
-            }
-            Err(_err) => Err(Error::Parse),
-        }
+        parse_uplink_downlink_counter(resp)
     }
 }
 
+/// 4.28.2 LW ULDL upload download counter set
+/// Set the upload and download counter, e.g. to restore them after a reset
+#[derive(Clone, Debug)]
+pub struct LoraUplinkDownlinkCounterSet {
+    pub uplink: u32,
+    pub downlink: u32,
+}
+
+impl AtatCmd for LoraUplinkDownlinkCounterSet {
+    type Response = UplinkDownlinkCounterGetResponse;
+
+    const MAX_LEN: usize = 36;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LW=ULDL,{},{}\r\n", self.uplink, self.downlink);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_uplink_downlink_counter(resp)
+    }
+}
+
+fn parse_uplink_downlink_counter(
+    resp: Result<&[u8], InternalError>,
+) -> Result<UplinkDownlinkCounterGetResponse, Error> {
+    let resp = lw_value(resp, "ULDL")?;
+    let (uplink, downlink) = resp.split_once(',').ok_or(Error::Parse)?;
+    Ok(UplinkDownlinkCounterGetResponse {
+        uplink: uplink.trim().parse().map_err(|_| Error::Parse)?,
+        downlink: downlink.trim().parse().map_err(|_| Error::Parse)?,
+    })
+}
+
+/// The value of a `+LW: <name>, <value>` response
+fn lw_value<'a>(resp: Result<&'a [u8], InternalError>, name: &str) -> Result<&'a str, Error> {
+    let resp = resp.map_err(Error::from)?;
+    let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+    let (field, value) = resp.split_once(',').ok_or(Error::Parse)?;
+    if field.trim() != name {
+        return Err(Error::Parse);
+    }
+    Ok(value.trim())
+}
+
 /// 4.28.12 LW Max payload length get
 /// Get the max length of the payload at the current data rate
-#[derive(Clone, Debug, AtatCmd)]
-#[at_cmd("+LW", MaxPayloadLengthGetResponse)]
+#[derive(Clone, Debug)]
 pub struct LoraMaxTxLengthGet {
     // LEN
     pub command: String<6>,
 }
 
+impl AtatCmd for LoraMaxTxLengthGet {
+    type Response = MaxPayloadLengthGetResponse;
+
+    const MAX_LEN: usize = 14;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LW={}\r\n", self.command);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let max = lw_value(resp, &self.command)?
+            .parse()
+            .map_err(|_| Error::Parse)?;
+        Ok(MaxPayloadLengthGetResponse {
+            command: self.command.clone(),
+            max,
+        })
+    }
+}
+
 impl Default for LoraMaxTxLengthGet {
     fn default() -> Self {
         Self {
@@ -674,3 +1255,276 @@ impl Default for LoraMaxTxLengthGet {
         }
     }
 }
+
+/// 4.28.3 LW DC/JDC/NET get
+/// Get whether duty cycle limitation, join duty cycle limitation or the public network sync word
+/// is on
+#[derive(Clone, Debug)]
+pub struct LoraWanSwitchGet {
+    pub switch: LoraWanSwitch,
+}
+
+impl AtatCmd for LoraWanSwitchGet {
+    type Response = LoraWanSwitchResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LW={}\r\n", self.switch.as_str());
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_switch(resp, self.switch)
+    }
+}
+
+/// 4.28.3 LW DC/JDC/NET set
+/// Turn duty cycle limitation, join duty cycle limitation or the public network sync word on or
+/// off
+#[derive(Clone, Debug)]
+pub struct LoraWanSwitchSet {
+    pub switch: LoraWanSwitch,
+    pub on: bool,
+}
+
+impl AtatCmd for LoraWanSwitchSet {
+    type Response = LoraWanSwitchResponse;
+
+    const MAX_LEN: usize = 15;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let on = if self.on { "ON" } else { "OFF" };
+        let _ = write!(buf, "AT+LW={},{}\r\n", self.switch.as_str(), on);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_switch(resp, self.switch)
+    }
+}
+
+/// `DC, ON` or `DC, OFF, 0`; anything after the switch state is ignored
+fn parse_switch(
+    resp: Result<&[u8], InternalError>,
+    switch: LoraWanSwitch,
+) -> Result<LoraWanSwitchResponse, Error> {
+    let value = lw_value(resp, switch.as_str())?;
+    let on = match value.split(',').next().map(str::trim) {
+        Some("ON") => true,
+        Some("OFF") => false,
+        _ => return Err(Error::Parse),
+    };
+    Ok(LoraWanSwitchResponse { on })
+}
+
+/// 4.28.4 LW MC get
+/// Get the multicast group DevAddr, if multicast is on
+#[derive(Clone, Debug)]
+pub struct MulticastGet {}
+
+impl AtatCmd for MulticastGet {
+    type Response = MulticastResponse;
+
+    const MAX_LEN: usize = 10;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..10].copy_from_slice(b"AT+LW=MC\r\n");
+        10
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_multicast(resp)
+    }
+}
+
+/// 4.28.4 LW MC set
+/// Join a multicast group with its DevAddr, McNwkSKey and McAppSKey
+#[derive(Clone, Debug)]
+pub struct MulticastSet {
+    pub dev_addr: u32,
+    pub nwk_s_key: u128,
+    pub app_s_key: u128,
+}
+
+impl AtatCmd for MulticastSet {
+    type Response = MulticastResponse;
+
+    const MAX_LEN: usize = 99;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(
+            buf,
+            "AT+LW=MC,ON,\"{:08X}\",\"{:032X}\",\"{:032X}\"\r\n",
+            self.dev_addr, self.nwk_s_key, self.app_s_key
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_multicast(resp)
+    }
+}
+
+/// 4.28.4 LW MC off
+/// Leave the multicast group
+#[derive(Clone, Debug)]
+pub struct MulticastDisable {}
+
+impl AtatCmd for MulticastDisable {
+    type Response = MulticastResponse;
+
+    const MAX_LEN: usize = 14;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..14].copy_from_slice(b"AT+LW=MC,OFF\r\n");
+        14
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_multicast(resp)
+    }
+}
+
+/// `MC, OFF` or `MC, ON, <DevAddr>`
+fn parse_multicast(resp: Result<&[u8], InternalError>) -> Result<MulticastResponse, Error> {
+    let value = lw_value(resp, "MC")?;
+    let mut parts = value.split(',').map(str::trim);
+    let dev_addr = match parts.next() {
+        Some("OFF") => None,
+        Some("ON") => {
+            let dev_addr = parts.next().ok_or(Error::Parse)?.trim_matches('"');
+            Some(u32::from_str_radix(dev_addr, 16).map_err(|_| Error::Parse)?)
+        }
+        _ => return Err(Error::Parse),
+    };
+    Ok(MulticastResponse { dev_addr })
+}
+
+/// 4.28.6 LW VER get
+/// Get the LoRaWAN version the modem follows
+#[derive(Clone, Debug)]
+pub struct LoraVersionGet {}
+
+impl AtatCmd for LoraVersionGet {
+    type Response = LoraVersionGetSetResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+LW=VER\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_version(resp)
+    }
+}
+
+/// 4.28.6 LW VER set
+/// Set the LoRaWAN version the modem follows
+#[derive(Clone, Debug)]
+pub struct LoraVersionSet {
+    pub version: LoraVersion,
+}
+
+impl AtatCmd for LoraVersionSet {
+    type Response = LoraVersionGetSetResponse;
+
+    const MAX_LEN: usize = 18;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LW=VER,{}\r\n", self.version.as_str());
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_version(resp)
+    }
+}
+
+fn parse_version(resp: Result<&[u8], InternalError>) -> Result<LoraVersionGetSetResponse, Error> {
+    let version = LoraVersion::from_str(lw_value(resp, "VER")?).map_err(|_| Error::Parse)?;
+    Ok(LoraVersionGetSetResponse { version })
+}
+
+/// 4.28.9 LW CDR
+/// Get the data rate the next uplink is sent at
+#[derive(Clone, Debug)]
+pub struct LoraCurrentDataRateGet {}
+
+impl AtatCmd for LoraCurrentDataRateGet {
+    type Response = CurrentDataRateResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+LW=CDR\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let data_rate = DataRate::from_str(lw_value(resp, "CDR")?).map_err(|_| Error::Parse)?;
+        Ok(CurrentDataRateResponse { data_rate })
+    }
+}
+
+/// 4.28.10 LW BAT get
+/// Get the battery level reported to the network server in DevStatusAns
+#[derive(Clone, Debug)]
+pub struct LoraBatteryLevelGet {}
+
+impl AtatCmd for LoraBatteryLevelGet {
+    type Response = BatteryLevelGetSetResponse;
+
+    const MAX_LEN: usize = 11;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..11].copy_from_slice(b"AT+LW=BAT\r\n");
+        11
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_battery_level(resp)
+    }
+}
+
+/// 4.28.10 LW BAT set
+/// Set the battery level reported to the network server in DevStatusAns.
+/// 0 is external power, 1 to 254 the battery level and 255 unknown.
+#[derive(Clone, Debug)]
+pub struct LoraBatteryLevelSet {
+    pub level: u8,
+}
+
+impl AtatCmd for LoraBatteryLevelSet {
+    type Response = BatteryLevelGetSetResponse;
+
+    const MAX_LEN: usize = 15;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+LW=BAT,{}\r\n", self.level);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        parse_battery_level(resp)
+    }
+}
+
+fn parse_battery_level(
+    resp: Result<&[u8], InternalError>,
+) -> Result<BatteryLevelGetSetResponse, Error> {
+    let level = lw_value(resp, "BAT")?.parse().map_err(|_| Error::Parse)?;
+    Ok(BatteryLevelGetSetResponse { level })
+}
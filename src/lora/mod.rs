@@ -1,146 +1,427 @@
 pub mod commands;
 pub mod responses;
+pub mod router;
 pub mod types;
 pub mod urc;
 
+use crate::error::LoraE5Error;
+use embassy_time::Duration;
+
+/// RX2 delay of the module until it is read or set
+pub(crate) const DEFAULT_RX2_DELAY_MS: u16 = 2000;
+
+/// Airtime of an uplink and the length of its RX2 window, on top of the RX2 delay
+const UPLINK_MARGIN: Duration = Duration::from_millis(3000);
+
+/// How long a join can take to end: the join request, the join accept delays (6 s for JRX2 by
+/// default) and the JRX2 window
+pub(crate) const JOIN_TIMEOUT: Duration = Duration::from_millis(15000);
+
+/// Two Class B beacon periods of 128 s, the module reports a lost beacon by then
+#[cfg(feature = "blocking")]
+pub(crate) const BEACON_TIMEOUT: Duration = Duration::from_millis(256000);
+
+/// How long `transmissions` uplinks can take to end in a `Done` URC, each of them waiting out
+/// its RX windows
+pub(crate) fn uplink_timeout(rx2_delay_ms: u16, transmissions: u8) -> Duration {
+    (Duration::from_millis(rx2_delay_ms as u64) + UPLINK_MARGIN) * transmissions.max(1) as u32
+}
+
+/// Check a value echoed by the module against the value that was set
+pub(crate) fn verify_echo<T: PartialEq>(echoed: T, expected: T) -> Result<(), LoraE5Error> {
+    if echoed == expected {
+        Ok(())
+    } else {
+        Err(LoraE5Error::EchoMismatch)
+    }
+}
+
 #[cfg(feature = "async")]
 pub mod asynch {
     use crate::client::asynch::{JoinStatus, SeeedLoraE5Client};
+    use crate::error::LoraE5Error;
     use crate::lora::types::LoraJoinMode;
     use crate::lora::{
         commands,
-        types::{LoraClass, LoraJoiningStatus, LoraRegion},
+        responses::MAX_CHANNELS,
+        router::DownlinkRouter,
+        types::{
+            BeaconChannel, BeaconStatus, DataRate, DataRateInfo, LinkCheckResult, LoraChannel,
+            LoraClass, LoraJoiningStatus, LoraRegion, LoraVersion, LoraWanSwitch, ReceiveDelay,
+            ReceiveDelays, Rx1Channel, Rx2Window, MAX_DATA_RATE, MAX_PING_SLOT_PERIODICITY,
+            MAX_UPLINK_LEN,
+        },
+        uplink_timeout, verify_echo, JOIN_TIMEOUT,
     };
-    use crate::urc::{
-        MessageStats, ReceivedMessage, LAST_LORA_MESSAGE_RECEIVED, LORA_JOIN_STATUS,
-        LORA_MESSAGE_RECEIVED_COUNT, LORA_MESSAGE_RECEIVED_STATS,
-    };
-    use atat::asynch::AtatClient;
+    use crate::urc::{MessageStats, ReceivedMessage, SendReport};
     use atat::Error;
+    use core::ops::RangeInclusive;
     use core::str::FromStr;
+    use embassy_time::with_timeout;
     use embedded_io_async::Write;
     use heapless::{String, Vec};
-    use serde_at::HexStr;
-
-    static mut CONFIRMED_SENDING: Option<bool> = Some(false);
 
-    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize> SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE> {
-        pub async fn join_mode(&mut self) -> Result<LoraJoinMode, Error> {
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub async fn join_mode(&mut self) -> Result<LoraJoinMode, LoraE5Error> {
             let command = commands::ModeGet {};
             let response = self.client.send(&command).await?;
             Ok(LoraJoinMode::from(response))
         }
 
-        pub async fn join_mode_set(&mut self, mode: LoraJoinMode) -> Result<LoraJoinMode, Error> {
+        pub async fn join_mode_set(
+            &mut self,
+            mode: LoraJoinMode,
+        ) -> Result<LoraJoinMode, LoraE5Error> {
             let command = match mode {
                 LoraJoinMode::Otaa => commands::ModeSet::otaa(),
                 LoraJoinMode::Abp => commands::ModeSet::abp(),
                 LoraJoinMode::Test => commands::ModeSet::test(),
-                _ => return Err(Error::Error),
+                _ => return Err(LoraE5Error::InvalidParameter),
             };
             let response = self.client.send(&command).await?;
             Ok(response.mode())
         }
 
-        pub async fn dev_eui(&mut self) -> Result<u64, Error> {
+        pub async fn dev_eui(&mut self) -> Result<u64, LoraE5Error> {
             let command = commands::DevEuiGet {};
             let response = self.client.send(&command).await?;
             Ok(response.dev_eui.val)
         }
 
-        pub async fn dev_eui_set(&mut self, dev_eui: u64) -> Result<u64, Error> {
+        pub async fn dev_eui_set(&mut self, dev_eui: u64) -> Result<u64, LoraE5Error> {
             let command = commands::DevEuiSet::dev_eui(dev_eui);
             let response = self.client.send(&command).await?;
             Ok(response.dev_eui.val)
         }
 
-        pub async fn app_eui(&mut self) -> Result<u64, Error> {
+        pub async fn app_eui(&mut self) -> Result<u64, LoraE5Error> {
             let command = commands::AppEuiGet {};
             let response = self.client.send(&command).await?;
             Ok(response.app_eui.val)
         }
 
-        pub async fn app_eui_set(&mut self, app_eui: u64) -> Result<u64, Error> {
+        pub async fn app_eui_set(&mut self, app_eui: u64) -> Result<u64, LoraE5Error> {
             let command = commands::AppEuiSet::app_eui(app_eui);
             let response = self.client.send(&command).await?;
             Ok(response.app_eui.val)
         }
 
-        pub async fn app_key_set(&mut self, app_key: u128) -> Result<(), Error> {
+        pub async fn app_key_set(&mut self, app_key: u128) -> Result<(), LoraE5Error> {
             let command = commands::AppKeySet::app_key(app_key);
-            self.client.send(&command).await?;
-            Ok(())
+            let response = self.client.send(&command).await?;
+            verify_echo(response.key.val, app_key)
         }
 
-        pub async fn lora_region(&mut self) -> Result<LoraRegion, Error> {
-            let command = commands::LoraDrGet {};
+        pub async fn dev_addr(&mut self) -> Result<u32, LoraE5Error> {
+            let command = commands::AbpDevAddrGet {};
             let response = self.client.send(&command).await?;
-            let s = response.rate.as_str();
-            let s: String<24> = String::from_str(s).map_err(|_| Error::Parse)?;
-            Ok(s.into())
+            Ok(response.dev_addr.val)
+        }
+
+        pub async fn dev_addr_set(&mut self, dev_addr: u32) -> Result<u32, LoraE5Error> {
+            let command = commands::AbpDevAddSet::dev_addr(dev_addr);
+            let response = self.client.send(&command).await?;
+            verify_echo(response.dev_addr.val, dev_addr)?;
+            Ok(dev_addr)
+        }
+
+        /// Set the ABP network session key.
+        /// The module does not report session keys back, other than echoing them when set.
+        pub async fn nwk_s_key_set(&mut self, nwk_s_key: u128) -> Result<(), LoraE5Error> {
+            let command = commands::NwkSKeySet::nwk_s_key(nwk_s_key);
+            let response = self.client.send(&command).await?;
+            verify_echo(response.key.val, nwk_s_key)
+        }
+
+        /// Set the ABP application session key.
+        /// The module does not report session keys back, other than echoing them when set.
+        pub async fn app_s_key_set(&mut self, app_s_key: u128) -> Result<(), LoraE5Error> {
+            let command = commands::AppSKeySet::app_s_key(app_s_key);
+            let response = self.client.send(&command).await?;
+            verify_echo(response.key.val, app_s_key)
+        }
+
+        /// Switch to ABP and provision the DevAddr and session keys.
+        /// Every value is checked against what the module echoes back.
+        pub async fn abp_activate(
+            &mut self,
+            dev_addr: u32,
+            nwk_s_key: u128,
+            app_s_key: u128,
+        ) -> Result<(), LoraE5Error> {
+            let mode = self.join_mode_set(LoraJoinMode::Abp).await?;
+            verify_echo(mode, LoraJoinMode::Abp)?;
+            self.dev_addr_set(dev_addr).await?;
+            self.nwk_s_key_set(nwk_s_key).await?;
+            self.app_s_key_set(app_s_key).await
+        }
+
+        pub async fn lora_region(&mut self) -> Result<LoraRegion, LoraE5Error> {
+            Ok(self.dr().await?.region)
         }
 
-        pub async fn lora_region_set(&mut self, region: LoraRegion) -> Result<LoraRegion, Error> {
+        pub async fn lora_region_set(
+            &mut self,
+            region: LoraRegion,
+        ) -> Result<LoraRegion, LoraE5Error> {
             let command = commands::DataRateSchemeSet::region(region);
             let response = self.client.send(&command).await?;
+            self.max_tx_len_cached = None;
             let s = response.rate.as_str();
             let s: String<24> = String::from_str(s).map_err(|_| Error::Parse)?;
             Ok(s.into())
         }
 
-        pub async fn lora_class(&mut self) -> Result<LoraClass, Error> {
+        /// Channels of the current channel plan
+        pub async fn channels(&mut self) -> Result<Vec<LoraChannel, MAX_CHANNELS>, LoraE5Error> {
+            let command = commands::ChannelsGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.channels)
+        }
+
+        /// Add or change channel `index`
+        pub async fn channel_set(
+            &mut self,
+            index: u8,
+            frequency_hz: u32,
+            dr_min: u8,
+            dr_max: u8,
+        ) -> Result<LoraChannel, LoraE5Error> {
+            let command = commands::ChannelSet {
+                index,
+                frequency_hz,
+                dr_min,
+                dr_max,
+            };
+            let response = self.client.send(&command).await?;
+            Ok(response.channel)
+        }
+
+        pub async fn channel_enable_set(
+            &mut self,
+            index: u8,
+            on: bool,
+        ) -> Result<bool, LoraE5Error> {
+            let command = commands::ChannelEnableSet { index, on };
+            let response = self.client.send(&command).await?;
+            verify_echo((response.index, response.on), (index, on))?;
+            Ok(response.on)
+        }
+
+        /// Only use the 8 channels of 125 kHz sub-band `sub_band` (1 to 8), e.g. sub-band 2 is
+        /// channels 8 to 15. For US915, AU915 and CN470.
+        pub async fn channel_mask_set(
+            &mut self,
+            sub_band: u8,
+        ) -> Result<RangeInclusive<u8>, LoraE5Error> {
+            if !(1..=8).contains(&sub_band) {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            let first = (sub_band - 1) * 8;
+            let command = commands::ChannelNumSet {
+                first,
+                last: first + 7,
+            };
+            let response = self.client.send(&command).await?;
+            Ok(response.first..=response.last)
+        }
+
+        /// Custom RX1 channel frequencies
+        pub async fn rx1_channels(&mut self) -> Result<Vec<Rx1Channel, 8>, LoraE5Error> {
+            let command = commands::Rx1ChannelsGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.channels)
+        }
+
+        pub async fn rx1_channel_set(
+            &mut self,
+            index: u8,
+            frequency_hz: u32,
+        ) -> Result<Rx1Channel, LoraE5Error> {
+            let command = commands::Rx1ChannelSet {
+                index,
+                frequency_hz,
+            };
+            let response = self.client.send(&command).await?;
+            Ok(response.channel)
+        }
+
+        pub async fn rx2_window(&mut self) -> Result<Rx2Window, LoraE5Error> {
+            let command = commands::Rx2WindowGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.window)
+        }
+
+        pub async fn rx2_window_set(
+            &mut self,
+            frequency_hz: u32,
+            data_rate: DataRate,
+        ) -> Result<Rx2Window, LoraE5Error> {
+            let command = commands::Rx2WindowSet {
+                frequency_hz,
+                data_rate,
+            };
+            let response = self.client.send(&command).await?;
+            Ok(response.window)
+        }
+
+        pub async fn receive_delays(&mut self) -> Result<ReceiveDelays, LoraE5Error> {
+            let command = commands::ReceiveDelaysGet {};
+            let response = self.client.send(&command).await?;
+            self.rx2_delay_ms = response.delays.rx2_ms;
+            Ok(response.delays)
+        }
+
+        /// Set all receive delays, checking every echoed delay and reading them back after
+        pub async fn receive_delays_set(
+            &mut self,
+            delays: ReceiveDelays,
+        ) -> Result<ReceiveDelays, LoraE5Error> {
+            if !delays.is_valid() {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            for delay in [
+                ReceiveDelay::Rx1,
+                ReceiveDelay::Rx2,
+                ReceiveDelay::JoinRx1,
+                ReceiveDelay::JoinRx2,
+            ] {
+                let ms = delays.get(delay);
+                let command = commands::ReceiveDelaySet { delay, ms };
+                let response = self.client.send(&command).await?;
+                verify_echo((response.delay, response.ms), (delay, ms))?;
+            }
+            let read_back = self.receive_delays().await?;
+            verify_echo(&read_back, &delays)?;
+            Ok(read_back)
+        }
+
+        /// Class B beacon frequency and data rate
+        pub async fn beacon(&mut self) -> Result<BeaconChannel, LoraE5Error> {
+            let command = commands::BeaconGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.channel)
+        }
+
+        pub async fn beacon_set(
+            &mut self,
+            frequency_hz: u32,
+            data_rate: DataRate,
+        ) -> Result<BeaconChannel, LoraE5Error> {
+            let command = commands::BeaconSet {
+                frequency_hz,
+                data_rate,
+            };
+            let response = self.client.send(&command).await?;
+            verify_echo(
+                &response.channel,
+                &BeaconChannel {
+                    frequency_hz,
+                    data_rate,
+                },
+            )?;
+            Ok(response.channel)
+        }
+
+        /// Query the Class B beacon acquisition state
+        pub async fn beacon_status(&mut self) -> Result<BeaconStatus, LoraE5Error> {
+            let command = commands::BeaconStatusGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.status)
+        }
+
+        /// Wait for the module to report a change of the Class B beacon state from now on,
+        /// e.g. while acquiring the beacon after switching to Class B with
+        /// [lora_class_set](Self::lora_class_set)
+        pub async fn beacon_status_wait(&mut self) -> Result<BeaconStatus, LoraE5Error> {
+            self.state.beacon_status_changed.reset();
+            Ok(self.state.beacon_status_changed.wait().await)
+        }
+
+        pub async fn ping_slot_periodicity(&mut self) -> Result<u8, LoraE5Error> {
+            let command = commands::PingSlotPeriodicityGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.periodicity)
+        }
+
+        /// Set the ping slot periodicity, ping slots open every `2^periodicity` seconds (0 to 7)
+        pub async fn ping_slot_periodicity_set(
+            &mut self,
+            periodicity: u8,
+        ) -> Result<u8, LoraE5Error> {
+            if periodicity > MAX_PING_SLOT_PERIODICITY {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            let command = commands::PingSlotPeriodicitySet { periodicity };
+            let response = self.client.send(&command).await?;
+            verify_echo(response.periodicity, periodicity)?;
+            Ok(response.periodicity)
+        }
+
+        pub async fn lora_class(&mut self) -> Result<LoraClass, LoraE5Error> {
             let command = commands::LoraClassGet {};
             let response = self.client.send(&command).await?;
             Ok(response.class.into())
         }
 
-        pub async fn lora_class_set(&mut self, class: LoraClass) -> Result<LoraClass, Error> {
+        pub async fn lora_class_set(&mut self, class: LoraClass) -> Result<LoraClass, LoraE5Error> {
             let command = commands::LoraClassSet::class(class);
             let response = self.client.send(&command).await?;
             Ok(response.class.into())
         }
 
-        pub async fn lora_join_otaa(&mut self) -> Result<LoraJoiningStatus, Error> {
+        pub async fn lora_join_otaa(&mut self) -> Result<LoraJoiningStatus, LoraE5Error> {
             self.join_status.join_status = JoinStatus::Joining;
-            LORA_JOIN_STATUS.signal(JoinStatus::Joining);
+            self.state.join_started();
+            self.max_tx_len_cached = None;
             let command = commands::LoraJoinOtaa {};
             let response = self
                 .client
                 .send(&command)
                 .await
-                .map_err(|e| {
-                    LORA_JOIN_STATUS.signal(JoinStatus::NotJoined);
+                .inspect_err(|_| {
+                    self.state.join_status.signal(JoinStatus::NotJoined);
                     self.join_status.join_status = JoinStatus::NotJoined;
-                    e
                 })?
                 .response;
             Ok(response.into())
         }
 
-        pub async fn lora_join_status(&mut self) -> Result<JoinStatus, Error> {
-            Ok(LORA_JOIN_STATUS
+        pub async fn lora_join_status(&mut self) -> Result<JoinStatus, LoraE5Error> {
+            Ok(self
+                .state
+                .join_status
                 .try_signaled_value()
                 .unwrap_or(JoinStatus::NotJoined))
         }
 
-        pub async fn lora_join_otaa_and_wait_for_result(&mut self) -> Result<JoinStatus, Error> {
+        pub async fn lora_join_otaa_and_wait_for_result(
+            &mut self,
+        ) -> Result<JoinStatus, LoraE5Error> {
             self.lora_join_otaa().await?;
-            loop {
-                let status = LORA_JOIN_STATUS.wait().await;
-                if matches!(
-                    status,
-                    JoinStatus::Success | JoinStatus::Failure | JoinStatus::NotJoined
-                ) {
-                    return Ok(status);
+            let state = self.state;
+            with_timeout(JOIN_TIMEOUT, async {
+                loop {
+                    let status = state.join_status.wait().await;
+                    if matches!(
+                        status,
+                        JoinStatus::Success | JoinStatus::Failure | JoinStatus::NotJoined
+                    ) {
+                        return status;
+                    }
                 }
-            }
+            })
+            .await
+            .map_err(|_| LoraE5Error::Timeout)
         }
 
         pub async fn auto_join_set(
             &mut self,
             is_on: bool,
             interval: u32,
-        ) -> Result<String<26>, Error> {
+        ) -> Result<String<26>, LoraE5Error> {
             let response = if is_on {
                 let command = commands::LoraAutoJoinOtaaMode0 { interval };
                 self.client.send(&command).await?
@@ -151,119 +432,1123 @@ pub mod asynch {
             Ok(response.response)
         }
 
-        pub async fn max_tx_len(&mut self) -> Result<u8, Error> {
+        pub async fn max_tx_len(&mut self) -> Result<u8, LoraE5Error> {
             let command = commands::LoraMaxTxLengthGet::default();
             let response = self.client.send(&command).await?;
+            self.max_tx_len_cached = Some(response.max);
             Ok(response.max)
         }
 
-        pub async fn confirm_send(&mut self) -> Result<bool, Error> {
-            let confirmed_sending = unsafe { CONFIRMED_SENDING.unwrap() };
-            Ok(confirmed_sending)
+        /// Whether [send](Self::send) sends confirmed uplinks
+        pub async fn confirm_send(&mut self) -> Result<bool, LoraE5Error> {
+            Ok(self.confirmed_sending)
         }
 
-        pub async fn confirm_send_set(&mut self, is_on: bool) -> Result<bool, Error> {
-            unsafe {
-                CONFIRMED_SENDING = Some(is_on);
-            }
+        /// Set whether [send](Self::send) sends confirmed uplinks
+        pub async fn confirm_send_set(&mut self, is_on: bool) -> Result<bool, LoraE5Error> {
+            self.confirmed_sending = is_on;
             Ok(is_on)
         }
 
+        /// Wait for the `Done` (or error) URC that ends the last (C)MSGHEX exchange of up to
+        /// `transmissions` uplinks, failing with [Timeout](LoraE5Error::Timeout) if it never comes
+        async fn send_result_wait(
+            &mut self,
+            confirmed: bool,
+            transmissions: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            let tracker = if confirmed {
+                &self.state.confirmed_send
+            } else {
+                &self.state.unconfirmed_send
+            };
+            let timeout = uplink_timeout(self.rx2_delay_ms, transmissions);
+            let result = with_timeout(timeout, tracker.result.wait())
+                .await
+                .map_err(|_| LoraE5Error::Timeout)?;
+            // ADR may have lowered the data rate, and the payload limit with it
+            if matches!(result, Err(LoraE5Error::LengthError)) {
+                self.max_tx_len_cached = None;
+            }
+            result
+        }
+
+        /// Check an uplink payload length against [max_tx_len](Self::max_tx_len), read only
+        /// when the data rate may have changed since it was last read
+        async fn uplink_len_check(&mut self, len: usize) -> Result<(), LoraE5Error> {
+            if len > MAX_UPLINK_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            let max = match self.max_tx_len_cached {
+                Some(max) => max,
+                None => self.max_tx_len().await?,
+            };
+            if len > max as usize {
+                return Err(LoraE5Error::LengthError);
+            }
+            Ok(())
+        }
+
+        /// Send a confirmed or unconfirmed uplink, as set by [confirm_send_set](Self::confirm_send_set).
+        /// `retransmission_times` is the number of retries (confirmed) or repeats (unconfirmed).
         pub async fn send(
             &mut self,
             retransmission_times: u8,
             port: u8,
             data: &[u8],
-        ) -> Result<(), Error> {
-            let mut val = [0u8; 242];
-            for (place, array) in val.iter_mut().zip(data.iter()) {
-                *place = *array;
+        ) -> Result<SendReport, LoraE5Error> {
+            if self.confirmed_sending {
+                self.send_confirmed(port, data, retransmission_times).await
+            } else {
+                self.send_unconfirmed(port, data, retransmission_times)
+                    .await
             }
+        }
 
-            let message = HexStr {
-                val,
-                add_0x_with_encoding: false,
-                hex_in_caps: false,
-                delimiter_after_nibble_count: 0,
-                delimiter: ' ',
-                skip_last_0_values: true,
-            };
+        /// Send a confirmed uplink (`AT+CMSGHEX`), retried up to `retries` times until acknowledged.
+        /// Fails with [LengthError](LoraE5Error::LengthError) if `data` is longer than
+        /// [max_tx_len](Self::max_tx_len).
+        pub async fn send_confirmed(
+            &mut self,
+            port: u8,
+            data: &[u8],
+            retries: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            self.uplink_len_check(data.len()).await?;
             let port_set = commands::LoraPortSet { port };
             let _response = self.client.send(&port_set).await?;
-            match self.confirm_send().await? {
-                true => {
-                    let retry = commands::RetrySet {
-                        retry: retransmission_times,
-                    };
-                    let _response = self.client.send(&retry).await?;
-                    let command = commands::MessageHexConfirmed { message };
-                    let _response = self.client.send(&command).await?;
-                    Ok(())
+            let retry = commands::RetrySet { retry: retries };
+            let _response = self.client.send(&retry).await?;
+            let command = commands::MessageHexConfirmed { data };
+            self.state.confirmed_send.result.reset();
+            let _response = self.client.send(&command).await?;
+            self.send_result_wait(true, retries.saturating_add(1)).await
+        }
+
+        /// Send an unconfirmed uplink (`AT+MSGHEX`), repeated `repeats` times.
+        /// Fails with [LengthError](LoraE5Error::LengthError) if `data` is longer than
+        /// [max_tx_len](Self::max_tx_len).
+        pub async fn send_unconfirmed(
+            &mut self,
+            port: u8,
+            data: &[u8],
+            repeats: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            self.uplink_len_check(data.len()).await?;
+            let port_set = commands::LoraPortSet { port };
+            let _response = self.client.send(&port_set).await?;
+            let repeat = commands::RepeatSet { repeat: repeats };
+            let _response = self.client.send(&repeat).await?;
+            let command = commands::MessageHexUnconfirmed { data };
+            self.state.unconfirmed_send.result.reset();
+            let _response = self.client.send(&command).await?;
+            self.send_result_wait(false, repeats.saturating_add(1))
+                .await
+        }
+
+        /// Send a link check request (empty `AT+MSG`).
+        /// Fails with [LoraE5Error::NoLinkCheckAnswer] if the network server did not answer, and
+        /// with [LoraE5Error::Timeout] if the module never ended the exchange with `Done`.
+        pub async fn link_check(&mut self) -> Result<LinkCheckResult, LoraE5Error> {
+            self.state.message_send.result.reset();
+            self.client.send(&commands::LinkCheck {}).await?;
+            let timeout = uplink_timeout(self.rx2_delay_ms, 1);
+            let report = with_timeout(timeout, self.state.message_send.result.wait())
+                .await
+                .map_err(|_| LoraE5Error::Timeout)??;
+            match report {
+                SendReport {
+                    link_check: Some((margin_db, gateway_count)),
+                    stats: Some(stats),
+                    ..
+                } => Ok(LinkCheckResult {
+                    margin_db,
+                    gateway_count,
+                    rssi: stats.rssi,
+                    snr: stats.snr,
+                }),
+                _ => Err(LoraE5Error::NoLinkCheckAnswer),
+            }
+        }
+
+        /// One step of a connectivity watchdog, to be scheduled by the caller: run a
+        /// [link check](Self::link_check) and count consecutive failures in `misses`.
+        /// Returns `Ok(None)` for a tolerated miss and the error of the failed check once
+        /// `max_misses` of them failed in a row.
+        pub async fn link_watchdog_tick(
+            &mut self,
+            misses: &mut u8,
+            max_misses: u8,
+        ) -> Result<Option<LinkCheckResult>, LoraE5Error> {
+            match self.link_check().await {
+                Ok(result) => {
+                    *misses = 0;
+                    Ok(Some(result))
                 }
-                false => {
-                    let repeat = commands::RepeatSet {
-                        repeat: retransmission_times,
-                    };
-                    let _response = self.client.send(&repeat).await?;
-                    let command = commands::MessageHexConfirmed { message };
-                    let _response = self.client.send(&command).await?;
-                    Ok(())
+                Err(e) => {
+                    *misses = misses.saturating_add(1);
+                    if *misses >= max_misses {
+                        Err(e)
+                    } else {
+                        Ok(None)
+                    }
                 }
             }
         }
 
-        pub async fn receive(&mut self) -> Result<(ReceivedMessage, MessageStats), Error> {
-            let value = LAST_LORA_MESSAGE_RECEIVED.wait().await;
-            LAST_LORA_MESSAGE_RECEIVED.reset();
-            let stats = LORA_MESSAGE_RECEIVED_STATS.wait().await;
-            LORA_MESSAGE_RECEIVED_STATS.reset();
-            Ok((value, stats))
+        /// Wait for the next queued downlink and its RX window stats
+        pub async fn receive(&mut self) -> Result<(ReceivedMessage, MessageStats), LoraE5Error> {
+            Ok(self.state.downlinks.receive().await)
+        }
+
+        /// The next queued downlink and its RX window stats, if any
+        pub fn try_receive(&mut self) -> Option<(ReceivedMessage, MessageStats)> {
+            self.state.downlinks.try_receive().ok()
         }
 
-        pub async fn adr_set(&mut self, on: bool) -> Result<bool, Error> {
+        /// Wait for the next queued downlink and dispatch it to its handler in `router`.
+        /// Returns whether a handler was called.
+        pub async fn receive_and_route<const ROUTES: usize>(
+            &mut self,
+            router: &mut DownlinkRouter<'_, ROUTES>,
+        ) -> Result<bool, LoraE5Error> {
+            let (message, stats) = self.receive().await?;
+            Ok(router.dispatch(&message, &stats))
+        }
+
+        pub async fn adr_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
             let command = if on {
                 commands::LoraAdrSet::on()
             } else {
                 commands::LoraAdrSet::off()
             };
             let response = self.client.send(&command).await?;
+            self.max_tx_len_cached = None;
             Ok(response.is_on())
         }
 
-        pub async fn dr_set(&mut self, data_rate: u8) -> Result<u8, Error> {
+        pub async fn dr(&mut self) -> Result<DataRateInfo, LoraE5Error> {
+            let command = commands::LoraDrGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.info()?)
+        }
+
+        /// Set the data rate, `0..=`[MAX_DATA_RATE]
+        pub async fn dr_set(&mut self, data_rate: u8) -> Result<DataRateInfo, LoraE5Error> {
+            if data_rate > MAX_DATA_RATE {
+                return Err(LoraE5Error::InvalidParameter);
+            }
             let command = commands::LoraDrSet::new(data_rate);
-            let _response = self.client.send(&command).await?;
-            Ok(data_rate)
+            let response = self.client.send(&command).await?;
+            self.max_tx_len_cached = None;
+            Ok(response.info()?)
         }
 
-        pub async fn uplink_frame_count(&mut self) -> Result<u32, Error> {
+        pub async fn uplink_frame_count(&mut self) -> Result<u32, LoraE5Error> {
             let command = commands::LoraUplinkDownlinkCounterGet {};
             let response = self.client.send(&command).await?;
             Ok(response.uplink())
         }
 
-        pub async fn downlink_frame_count(&mut self) -> Result<u32, Error> {
+        pub async fn downlink_frame_count(&mut self) -> Result<u32, LoraE5Error> {
             let command = commands::LoraUplinkDownlinkCounterGet {};
             let response = self.client.send(&command).await?;
             Ok(response.downlink())
         }
 
-        pub async fn downlink_message_count(&self) -> Result<u32, Error> {
-            Ok(LORA_MESSAGE_RECEIVED_COUNT
+        /// Restore the uplink and downlink frame counters, e.g. after a reset
+        pub async fn uplink_downlink_counter_set(
+            &mut self,
+            uplink: u32,
+            downlink: u32,
+        ) -> Result<(u32, u32), LoraE5Error> {
+            let command = commands::LoraUplinkDownlinkCounterSet { uplink, downlink };
+            let response = self.client.send(&command).await?;
+            verify_echo((response.uplink, response.downlink), (uplink, downlink))?;
+            Ok((response.uplink, response.downlink))
+        }
+
+        async fn lorawan_switch(&mut self, switch: LoraWanSwitch) -> Result<bool, LoraE5Error> {
+            let command = commands::LoraWanSwitchGet { switch };
+            let response = self.client.send(&command).await?;
+            Ok(response.on)
+        }
+
+        async fn lorawan_switch_set(
+            &mut self,
+            switch: LoraWanSwitch,
+            on: bool,
+        ) -> Result<bool, LoraE5Error> {
+            let command = commands::LoraWanSwitchSet { switch, on };
+            let response = self.client.send(&command).await?;
+            verify_echo(response.on, on)?;
+            Ok(response.on)
+        }
+
+        /// Whether duty cycle limitation is on
+        pub async fn duty_cycle(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::DutyCycle).await
+        }
+
+        pub async fn duty_cycle_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::DutyCycle, on).await
+        }
+
+        /// Whether join duty cycle limitation is on
+        pub async fn join_duty_cycle(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::JoinDutyCycle).await
+        }
+
+        pub async fn join_duty_cycle_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::JoinDutyCycle, on)
+                .await
+        }
+
+        /// Whether the public network sync word is used, `false` for a private network
+        pub async fn public_network(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::PublicNetwork).await
+        }
+
+        pub async fn public_network_set(&mut self, public: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::PublicNetwork, public)
+                .await
+        }
+
+        /// DevAddr of the multicast group the device is in, if any
+        pub async fn multicast(&mut self) -> Result<Option<u32>, LoraE5Error> {
+            let command = commands::MulticastGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.dev_addr)
+        }
+
+        /// Join a multicast group. Its downlinks are received with
+        /// [ReceivedMessage::multicast](crate::urc::ReceivedMessage::multicast) set.
+        pub async fn multicast_set(
+            &mut self,
+            dev_addr: u32,
+            nwk_s_key: u128,
+            app_s_key: u128,
+        ) -> Result<u32, LoraE5Error> {
+            let command = commands::MulticastSet {
+                dev_addr,
+                nwk_s_key,
+                app_s_key,
+            };
+            let response = self.client.send(&command).await?;
+            verify_echo(response.dev_addr, Some(dev_addr))?;
+            Ok(dev_addr)
+        }
+
+        /// Leave the multicast group
+        pub async fn multicast_disable(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::MulticastDisable {};
+            let response = self.client.send(&command).await?;
+            verify_echo(response.dev_addr, None)
+        }
+
+        pub async fn lorawan_version(&mut self) -> Result<LoraVersion, LoraE5Error> {
+            let command = commands::LoraVersionGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.version)
+        }
+
+        pub async fn lorawan_version_set(
+            &mut self,
+            version: LoraVersion,
+        ) -> Result<LoraVersion, LoraE5Error> {
+            let command = commands::LoraVersionSet {
+                version: version.clone(),
+            };
+            let response = self.client.send(&command).await?;
+            verify_echo(&response.version, &version)?;
+            Ok(response.version)
+        }
+
+        /// Data rate the next uplink is sent at
+        pub async fn current_data_rate(&mut self) -> Result<DataRate, LoraE5Error> {
+            let command = commands::LoraCurrentDataRateGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.data_rate)
+        }
+
+        /// Battery level reported in DevStatusAns: 0 is external power, 1 to 254 the level and
+        /// 255 unknown
+        pub async fn battery_level(&mut self) -> Result<u8, LoraE5Error> {
+            let command = commands::LoraBatteryLevelGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.level)
+        }
+
+        pub async fn battery_level_set(&mut self, level: u8) -> Result<u8, LoraE5Error> {
+            let command = commands::LoraBatteryLevelSet { level };
+            let response = self.client.send(&command).await?;
+            verify_echo(response.level, level)?;
+            Ok(response.level)
+        }
+
+        pub async fn downlink_message_count(&self) -> Result<u32, LoraE5Error> {
+            Ok(self
+                .state
+                .message_received_count
                 .try_signaled_value()
                 .unwrap_or_default())
         }
 
-        pub async fn tx_power_force_set(&mut self, db_m: u8) -> Result<u8, Error> {
+        pub async fn tx_power_force_set(&mut self, db_m: u8) -> Result<u8, LoraE5Error> {
             let command = commands::TxPowerForceSet::new(db_m);
             let response = self.client.send(&command).await?;
             Ok(response.db_m)
         }
 
-        pub async fn tx_power_table(&mut self) -> Result<Vec<u8, 12>, Error> {
+        pub async fn tx_power_table(&mut self) -> Result<Vec<u8, 12>, LoraE5Error> {
             let command = commands::TxPowerTableGet::default();
             let response = self.client.send(&command).await?;
-            response.db_m_list()
+            Ok(response.db_m_list()?)
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    use crate::client::blocking::{poll_until, JoinStatus, SeeedLoraE5Client, RECEIVE_TIMEOUT};
+    use crate::error::LoraE5Error;
+    use crate::lora::types::LoraJoinMode;
+    use crate::lora::{
+        commands,
+        responses::MAX_CHANNELS,
+        router::DownlinkRouter,
+        types::{
+            BeaconChannel, BeaconStatus, DataRate, DataRateInfo, LinkCheckResult, LoraChannel,
+            LoraClass, LoraJoiningStatus, LoraRegion, LoraVersion, LoraWanSwitch, ReceiveDelay,
+            ReceiveDelays, Rx1Channel, Rx2Window, MAX_DATA_RATE, MAX_PING_SLOT_PERIODICITY,
+            MAX_UPLINK_LEN,
+        },
+        uplink_timeout, verify_echo, BEACON_TIMEOUT, JOIN_TIMEOUT,
+    };
+    use crate::urc::{MessageStats, ReceivedMessage, SendReport};
+    use atat::Error;
+    use core::ops::RangeInclusive;
+    use core::str::FromStr;
+    use embedded_io::Write;
+    use heapless::{String, Vec};
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub fn join_mode(&mut self) -> Result<LoraJoinMode, LoraE5Error> {
+            let command = commands::ModeGet {};
+            let response = self.client.send(&command)?;
+            Ok(LoraJoinMode::from(response))
+        }
+
+        pub fn join_mode_set(&mut self, mode: LoraJoinMode) -> Result<LoraJoinMode, LoraE5Error> {
+            let command = match mode {
+                LoraJoinMode::Otaa => commands::ModeSet::otaa(),
+                LoraJoinMode::Abp => commands::ModeSet::abp(),
+                LoraJoinMode::Test => commands::ModeSet::test(),
+                _ => return Err(LoraE5Error::InvalidParameter),
+            };
+            let response = self.client.send(&command)?;
+            Ok(response.mode())
+        }
+
+        pub fn dev_eui(&mut self) -> Result<u64, LoraE5Error> {
+            let command = commands::DevEuiGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.dev_eui.val)
+        }
+
+        pub fn dev_eui_set(&mut self, dev_eui: u64) -> Result<u64, LoraE5Error> {
+            let command = commands::DevEuiSet::dev_eui(dev_eui);
+            let response = self.client.send(&command)?;
+            Ok(response.dev_eui.val)
+        }
+
+        pub fn app_eui(&mut self) -> Result<u64, LoraE5Error> {
+            let command = commands::AppEuiGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.app_eui.val)
+        }
+
+        pub fn app_eui_set(&mut self, app_eui: u64) -> Result<u64, LoraE5Error> {
+            let command = commands::AppEuiSet::app_eui(app_eui);
+            let response = self.client.send(&command)?;
+            Ok(response.app_eui.val)
+        }
+
+        pub fn app_key_set(&mut self, app_key: u128) -> Result<(), LoraE5Error> {
+            let command = commands::AppKeySet::app_key(app_key);
+            let response = self.client.send(&command)?;
+            verify_echo(response.key.val, app_key)
+        }
+
+        pub fn dev_addr(&mut self) -> Result<u32, LoraE5Error> {
+            let command = commands::AbpDevAddrGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.dev_addr.val)
+        }
+
+        pub fn dev_addr_set(&mut self, dev_addr: u32) -> Result<u32, LoraE5Error> {
+            let command = commands::AbpDevAddSet::dev_addr(dev_addr);
+            let response = self.client.send(&command)?;
+            verify_echo(response.dev_addr.val, dev_addr)?;
+            Ok(dev_addr)
+        }
+
+        /// Set the ABP network session key.
+        /// The module does not report session keys back, other than echoing them when set.
+        pub fn nwk_s_key_set(&mut self, nwk_s_key: u128) -> Result<(), LoraE5Error> {
+            let command = commands::NwkSKeySet::nwk_s_key(nwk_s_key);
+            let response = self.client.send(&command)?;
+            verify_echo(response.key.val, nwk_s_key)
+        }
+
+        /// Set the ABP application session key.
+        /// The module does not report session keys back, other than echoing them when set.
+        pub fn app_s_key_set(&mut self, app_s_key: u128) -> Result<(), LoraE5Error> {
+            let command = commands::AppSKeySet::app_s_key(app_s_key);
+            let response = self.client.send(&command)?;
+            verify_echo(response.key.val, app_s_key)
+        }
+
+        /// Switch to ABP and provision the DevAddr and session keys.
+        /// Every value is checked against what the module echoes back.
+        pub fn abp_activate(
+            &mut self,
+            dev_addr: u32,
+            nwk_s_key: u128,
+            app_s_key: u128,
+        ) -> Result<(), LoraE5Error> {
+            let mode = self.join_mode_set(LoraJoinMode::Abp)?;
+            verify_echo(mode, LoraJoinMode::Abp)?;
+            self.dev_addr_set(dev_addr)?;
+            self.nwk_s_key_set(nwk_s_key)?;
+            self.app_s_key_set(app_s_key)
+        }
+
+        pub fn lora_region(&mut self) -> Result<LoraRegion, LoraE5Error> {
+            Ok(self.dr()?.region)
+        }
+
+        pub fn lora_region_set(&mut self, region: LoraRegion) -> Result<LoraRegion, LoraE5Error> {
+            let command = commands::DataRateSchemeSet::region(region);
+            let response = self.client.send(&command)?;
+            self.max_tx_len_cached = None;
+            let s = response.rate.as_str();
+            let s: String<24> = String::from_str(s).map_err(|_| Error::Parse)?;
+            Ok(s.into())
+        }
+
+        /// Channels of the current channel plan
+        pub fn channels(&mut self) -> Result<Vec<LoraChannel, MAX_CHANNELS>, LoraE5Error> {
+            let command = commands::ChannelsGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.channels)
+        }
+
+        /// Add or change channel `index`
+        pub fn channel_set(
+            &mut self,
+            index: u8,
+            frequency_hz: u32,
+            dr_min: u8,
+            dr_max: u8,
+        ) -> Result<LoraChannel, LoraE5Error> {
+            let command = commands::ChannelSet {
+                index,
+                frequency_hz,
+                dr_min,
+                dr_max,
+            };
+            let response = self.client.send(&command)?;
+            Ok(response.channel)
+        }
+
+        pub fn channel_enable_set(&mut self, index: u8, on: bool) -> Result<bool, LoraE5Error> {
+            let command = commands::ChannelEnableSet { index, on };
+            let response = self.client.send(&command)?;
+            verify_echo((response.index, response.on), (index, on))?;
+            Ok(response.on)
+        }
+
+        /// Only use the 8 channels of 125 kHz sub-band `sub_band` (1 to 8), e.g. sub-band 2 is
+        /// channels 8 to 15. For US915, AU915 and CN470.
+        pub fn channel_mask_set(
+            &mut self,
+            sub_band: u8,
+        ) -> Result<RangeInclusive<u8>, LoraE5Error> {
+            if !(1..=8).contains(&sub_band) {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            let first = (sub_band - 1) * 8;
+            let command = commands::ChannelNumSet {
+                first,
+                last: first + 7,
+            };
+            let response = self.client.send(&command)?;
+            Ok(response.first..=response.last)
+        }
+
+        /// Custom RX1 channel frequencies
+        pub fn rx1_channels(&mut self) -> Result<Vec<Rx1Channel, 8>, LoraE5Error> {
+            let command = commands::Rx1ChannelsGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.channels)
+        }
+
+        pub fn rx1_channel_set(
+            &mut self,
+            index: u8,
+            frequency_hz: u32,
+        ) -> Result<Rx1Channel, LoraE5Error> {
+            let command = commands::Rx1ChannelSet {
+                index,
+                frequency_hz,
+            };
+            let response = self.client.send(&command)?;
+            Ok(response.channel)
+        }
+
+        pub fn rx2_window(&mut self) -> Result<Rx2Window, LoraE5Error> {
+            let command = commands::Rx2WindowGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.window)
+        }
+
+        pub fn rx2_window_set(
+            &mut self,
+            frequency_hz: u32,
+            data_rate: DataRate,
+        ) -> Result<Rx2Window, LoraE5Error> {
+            let command = commands::Rx2WindowSet {
+                frequency_hz,
+                data_rate,
+            };
+            let response = self.client.send(&command)?;
+            Ok(response.window)
+        }
+
+        pub fn receive_delays(&mut self) -> Result<ReceiveDelays, LoraE5Error> {
+            let command = commands::ReceiveDelaysGet {};
+            let response = self.client.send(&command)?;
+            self.rx2_delay_ms = response.delays.rx2_ms;
+            Ok(response.delays)
+        }
+
+        /// Set all receive delays, checking every echoed delay and reading them back after
+        pub fn receive_delays_set(
+            &mut self,
+            delays: ReceiveDelays,
+        ) -> Result<ReceiveDelays, LoraE5Error> {
+            if !delays.is_valid() {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            for delay in [
+                ReceiveDelay::Rx1,
+                ReceiveDelay::Rx2,
+                ReceiveDelay::JoinRx1,
+                ReceiveDelay::JoinRx2,
+            ] {
+                let ms = delays.get(delay);
+                let command = commands::ReceiveDelaySet { delay, ms };
+                let response = self.client.send(&command)?;
+                verify_echo((response.delay, response.ms), (delay, ms))?;
+            }
+            let read_back = self.receive_delays()?;
+            verify_echo(&read_back, &delays)?;
+            Ok(read_back)
+        }
+
+        /// Class B beacon frequency and data rate
+        pub fn beacon(&mut self) -> Result<BeaconChannel, LoraE5Error> {
+            let command = commands::BeaconGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.channel)
+        }
+
+        pub fn beacon_set(
+            &mut self,
+            frequency_hz: u32,
+            data_rate: DataRate,
+        ) -> Result<BeaconChannel, LoraE5Error> {
+            let command = commands::BeaconSet {
+                frequency_hz,
+                data_rate,
+            };
+            let response = self.client.send(&command)?;
+            verify_echo(
+                &response.channel,
+                &BeaconChannel {
+                    frequency_hz,
+                    data_rate,
+                },
+            )?;
+            Ok(response.channel)
+        }
+
+        /// Query the Class B beacon acquisition state
+        pub fn beacon_status(&mut self) -> Result<BeaconStatus, LoraE5Error> {
+            let command = commands::BeaconStatusGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.status)
+        }
+
+        /// Wait for the module to report a change of the Class B beacon state from now on,
+        /// e.g. while acquiring the beacon after switching to Class B with
+        /// [lora_class_set](Self::lora_class_set).
+        /// Fails with [Timeout](LoraE5Error::Timeout) if it reported none within two beacon periods.
+        pub fn beacon_status_wait(&mut self) -> Result<BeaconStatus, LoraE5Error> {
+            self.state.beacon_status_changed.reset();
+            poll_until(BEACON_TIMEOUT, || {
+                self.state.beacon_status_changed.try_take()
+            })
+        }
+
+        pub fn ping_slot_periodicity(&mut self) -> Result<u8, LoraE5Error> {
+            let command = commands::PingSlotPeriodicityGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.periodicity)
+        }
+
+        /// Set the ping slot periodicity, ping slots open every `2^periodicity` seconds (0 to 7)
+        pub fn ping_slot_periodicity_set(&mut self, periodicity: u8) -> Result<u8, LoraE5Error> {
+            if periodicity > MAX_PING_SLOT_PERIODICITY {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            let command = commands::PingSlotPeriodicitySet { periodicity };
+            let response = self.client.send(&command)?;
+            verify_echo(response.periodicity, periodicity)?;
+            Ok(response.periodicity)
+        }
+
+        pub fn lora_class(&mut self) -> Result<LoraClass, LoraE5Error> {
+            let command = commands::LoraClassGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.class.into())
+        }
+
+        pub fn lora_class_set(&mut self, class: LoraClass) -> Result<LoraClass, LoraE5Error> {
+            let command = commands::LoraClassSet::class(class);
+            let response = self.client.send(&command)?;
+            Ok(response.class.into())
+        }
+
+        pub fn lora_join_otaa(&mut self) -> Result<LoraJoiningStatus, LoraE5Error> {
+            self.join_status.join_status = JoinStatus::Joining;
+            self.state.join_started();
+            self.max_tx_len_cached = None;
+            let command = commands::LoraJoinOtaa {};
+            let response = self
+                .client
+                .send(&command)
+                .inspect_err(|_| {
+                    self.state.join_status.signal(JoinStatus::NotJoined);
+                    self.join_status.join_status = JoinStatus::NotJoined;
+                })?
+                .response;
+            Ok(response.into())
+        }
+
+        pub fn lora_join_status(&mut self) -> Result<JoinStatus, LoraE5Error> {
+            Ok(self
+                .state
+                .join_status
+                .try_signaled_value()
+                .unwrap_or(JoinStatus::NotJoined))
+        }
+
+        pub fn lora_join_otaa_and_wait_for_result(&mut self) -> Result<JoinStatus, LoraE5Error> {
+            self.lora_join_otaa()?;
+            poll_until(JOIN_TIMEOUT, || {
+                self.state.join_status.try_take().filter(|status| {
+                    matches!(
+                        status,
+                        JoinStatus::Success | JoinStatus::Failure | JoinStatus::NotJoined
+                    )
+                })
+            })
+        }
+
+        pub fn auto_join_set(
+            &mut self,
+            is_on: bool,
+            interval: u32,
+        ) -> Result<String<26>, LoraE5Error> {
+            let response = if is_on {
+                let command = commands::LoraAutoJoinOtaaMode0 { interval };
+                self.client.send(&command)?
+            } else {
+                let command = commands::LoraAutoJoinOtaaDisable {};
+                self.client.send(&command)?
+            };
+            Ok(response.response)
+        }
+
+        pub fn max_tx_len(&mut self) -> Result<u8, LoraE5Error> {
+            let command = commands::LoraMaxTxLengthGet::default();
+            let response = self.client.send(&command)?;
+            self.max_tx_len_cached = Some(response.max);
+            Ok(response.max)
+        }
+
+        /// Whether [send](Self::send) sends confirmed uplinks
+        pub fn confirm_send(&mut self) -> Result<bool, LoraE5Error> {
+            Ok(self.confirmed_sending)
+        }
+
+        /// Set whether [send](Self::send) sends confirmed uplinks
+        pub fn confirm_send_set(&mut self, is_on: bool) -> Result<bool, LoraE5Error> {
+            self.confirmed_sending = is_on;
+            Ok(is_on)
+        }
+
+        /// Wait for the `Done` (or error) URC that ends the last (C)MSGHEX exchange of up to
+        /// `transmissions` uplinks, failing with [Timeout](LoraE5Error::Timeout) if it never comes
+        fn send_result_wait(
+            &mut self,
+            confirmed: bool,
+            transmissions: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            let tracker = if confirmed {
+                &self.state.confirmed_send
+            } else {
+                &self.state.unconfirmed_send
+            };
+            let timeout = uplink_timeout(self.rx2_delay_ms, transmissions);
+            let result = poll_until(timeout, || tracker.result.try_take())?;
+            // ADR may have lowered the data rate, and the payload limit with it
+            if matches!(result, Err(LoraE5Error::LengthError)) {
+                self.max_tx_len_cached = None;
+            }
+            result
+        }
+
+        /// Check an uplink payload length against [max_tx_len](Self::max_tx_len), read only
+        /// when the data rate may have changed since it was last read
+        fn uplink_len_check(&mut self, len: usize) -> Result<(), LoraE5Error> {
+            if len > MAX_UPLINK_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            let max = match self.max_tx_len_cached {
+                Some(max) => max,
+                None => self.max_tx_len()?,
+            };
+            if len > max as usize {
+                return Err(LoraE5Error::LengthError);
+            }
+            Ok(())
+        }
+
+        /// Send a confirmed or unconfirmed uplink, as set by [confirm_send_set](Self::confirm_send_set).
+        /// `retransmission_times` is the number of retries (confirmed) or repeats (unconfirmed).
+        pub fn send(
+            &mut self,
+            retransmission_times: u8,
+            port: u8,
+            data: &[u8],
+        ) -> Result<SendReport, LoraE5Error> {
+            if self.confirmed_sending {
+                self.send_confirmed(port, data, retransmission_times)
+            } else {
+                self.send_unconfirmed(port, data, retransmission_times)
+            }
+        }
+
+        /// Send a confirmed uplink (`AT+CMSGHEX`), retried up to `retries` times until acknowledged.
+        /// Fails with [LengthError](LoraE5Error::LengthError) if `data` is longer than
+        /// [max_tx_len](Self::max_tx_len).
+        pub fn send_confirmed(
+            &mut self,
+            port: u8,
+            data: &[u8],
+            retries: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            self.uplink_len_check(data.len())?;
+            let port_set = commands::LoraPortSet { port };
+            let _response = self.client.send(&port_set)?;
+            let retry = commands::RetrySet { retry: retries };
+            let _response = self.client.send(&retry)?;
+            let command = commands::MessageHexConfirmed { data };
+            self.state.confirmed_send.result.reset();
+            let _response = self.client.send(&command)?;
+            self.send_result_wait(true, retries.saturating_add(1))
+        }
+
+        /// Send an unconfirmed uplink (`AT+MSGHEX`), repeated `repeats` times.
+        /// Fails with [LengthError](LoraE5Error::LengthError) if `data` is longer than
+        /// [max_tx_len](Self::max_tx_len).
+        pub fn send_unconfirmed(
+            &mut self,
+            port: u8,
+            data: &[u8],
+            repeats: u8,
+        ) -> Result<SendReport, LoraE5Error> {
+            self.uplink_len_check(data.len())?;
+            let port_set = commands::LoraPortSet { port };
+            let _response = self.client.send(&port_set)?;
+            let repeat = commands::RepeatSet { repeat: repeats };
+            let _response = self.client.send(&repeat)?;
+            let command = commands::MessageHexUnconfirmed { data };
+            self.state.unconfirmed_send.result.reset();
+            let _response = self.client.send(&command)?;
+            self.send_result_wait(false, repeats.saturating_add(1))
+        }
+
+        /// Send a link check request (empty `AT+MSG`).
+        /// Fails with [LoraE5Error::NoLinkCheckAnswer] if the network server did not answer, and
+        /// with [LoraE5Error::Timeout] if the module never ended the exchange with `Done`.
+        pub fn link_check(&mut self) -> Result<LinkCheckResult, LoraE5Error> {
+            self.state.message_send.result.reset();
+            self.client.send(&commands::LinkCheck {})?;
+            let timeout = uplink_timeout(self.rx2_delay_ms, 1);
+            let report = poll_until(timeout, || self.state.message_send.result.try_take())??;
+            match report {
+                SendReport {
+                    link_check: Some((margin_db, gateway_count)),
+                    stats: Some(stats),
+                    ..
+                } => Ok(LinkCheckResult {
+                    margin_db,
+                    gateway_count,
+                    rssi: stats.rssi,
+                    snr: stats.snr,
+                }),
+                _ => Err(LoraE5Error::NoLinkCheckAnswer),
+            }
+        }
+
+        /// One step of a connectivity watchdog, to be scheduled by the caller: run a
+        /// [link check](Self::link_check) and count consecutive failures in `misses`.
+        /// Returns `Ok(None)` for a tolerated miss and the error of the failed check once
+        /// `max_misses` of them failed in a row.
+        pub fn link_watchdog_tick(
+            &mut self,
+            misses: &mut u8,
+            max_misses: u8,
+        ) -> Result<Option<LinkCheckResult>, LoraE5Error> {
+            match self.link_check() {
+                Ok(result) => {
+                    *misses = 0;
+                    Ok(Some(result))
+                }
+                Err(e) => {
+                    *misses = misses.saturating_add(1);
+                    if *misses >= max_misses {
+                        Err(e)
+                    } else {
+                        Ok(None)
+                    }
+                }
+            }
+        }
+
+        /// Wait for the next queued downlink and its RX window stats.
+        /// Fails with [Timeout](LoraE5Error::Timeout) if none came within [RECEIVE_TIMEOUT].
+        pub fn receive(&mut self) -> Result<(ReceivedMessage, MessageStats), LoraE5Error> {
+            poll_until(RECEIVE_TIMEOUT, || self.state.downlinks.try_receive().ok())
+        }
+
+        /// The next queued downlink and its RX window stats, if any
+        pub fn try_receive(&mut self) -> Option<(ReceivedMessage, MessageStats)> {
+            self.state.downlinks.try_receive().ok()
+        }
+
+        /// Wait for the next queued downlink and dispatch it to its handler in `router`.
+        /// Returns whether a handler was called.
+        pub fn receive_and_route<const ROUTES: usize>(
+            &mut self,
+            router: &mut DownlinkRouter<'_, ROUTES>,
+        ) -> Result<bool, LoraE5Error> {
+            let (message, stats) = self.receive()?;
+            Ok(router.dispatch(&message, &stats))
+        }
+
+        pub fn adr_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            let command = if on {
+                commands::LoraAdrSet::on()
+            } else {
+                commands::LoraAdrSet::off()
+            };
+            let response = self.client.send(&command)?;
+            self.max_tx_len_cached = None;
+            Ok(response.is_on())
+        }
+
+        pub fn dr(&mut self) -> Result<DataRateInfo, LoraE5Error> {
+            let command = commands::LoraDrGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.info()?)
+        }
+
+        /// Set the data rate, `0..=`[MAX_DATA_RATE]
+        pub fn dr_set(&mut self, data_rate: u8) -> Result<DataRateInfo, LoraE5Error> {
+            if data_rate > MAX_DATA_RATE {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            let command = commands::LoraDrSet::new(data_rate);
+            let response = self.client.send(&command)?;
+            self.max_tx_len_cached = None;
+            Ok(response.info()?)
+        }
+
+        pub fn uplink_frame_count(&mut self) -> Result<u32, LoraE5Error> {
+            let command = commands::LoraUplinkDownlinkCounterGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.uplink())
+        }
+
+        pub fn downlink_frame_count(&mut self) -> Result<u32, LoraE5Error> {
+            let command = commands::LoraUplinkDownlinkCounterGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.downlink())
+        }
+
+        /// Restore the uplink and downlink frame counters, e.g. after a reset
+        pub fn uplink_downlink_counter_set(
+            &mut self,
+            uplink: u32,
+            downlink: u32,
+        ) -> Result<(u32, u32), LoraE5Error> {
+            let command = commands::LoraUplinkDownlinkCounterSet { uplink, downlink };
+            let response = self.client.send(&command)?;
+            verify_echo((response.uplink, response.downlink), (uplink, downlink))?;
+            Ok((response.uplink, response.downlink))
+        }
+
+        fn lorawan_switch(&mut self, switch: LoraWanSwitch) -> Result<bool, LoraE5Error> {
+            let command = commands::LoraWanSwitchGet { switch };
+            let response = self.client.send(&command)?;
+            Ok(response.on)
+        }
+
+        fn lorawan_switch_set(
+            &mut self,
+            switch: LoraWanSwitch,
+            on: bool,
+        ) -> Result<bool, LoraE5Error> {
+            let command = commands::LoraWanSwitchSet { switch, on };
+            let response = self.client.send(&command)?;
+            verify_echo(response.on, on)?;
+            Ok(response.on)
+        }
+
+        /// Whether duty cycle limitation is on
+        pub fn duty_cycle(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::DutyCycle)
+        }
+
+        pub fn duty_cycle_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::DutyCycle, on)
+        }
+
+        /// Whether join duty cycle limitation is on
+        pub fn join_duty_cycle(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::JoinDutyCycle)
+        }
+
+        pub fn join_duty_cycle_set(&mut self, on: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::JoinDutyCycle, on)
+        }
+
+        /// Whether the public network sync word is used, `false` for a private network
+        pub fn public_network(&mut self) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch(LoraWanSwitch::PublicNetwork)
+        }
+
+        pub fn public_network_set(&mut self, public: bool) -> Result<bool, LoraE5Error> {
+            self.lorawan_switch_set(LoraWanSwitch::PublicNetwork, public)
+        }
+
+        /// DevAddr of the multicast group the device is in, if any
+        pub fn multicast(&mut self) -> Result<Option<u32>, LoraE5Error> {
+            let command = commands::MulticastGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.dev_addr)
+        }
+
+        /// Join a multicast group. Its downlinks are received with
+        /// [ReceivedMessage::multicast](crate::urc::ReceivedMessage::multicast) set.
+        pub fn multicast_set(
+            &mut self,
+            dev_addr: u32,
+            nwk_s_key: u128,
+            app_s_key: u128,
+        ) -> Result<u32, LoraE5Error> {
+            let command = commands::MulticastSet {
+                dev_addr,
+                nwk_s_key,
+                app_s_key,
+            };
+            let response = self.client.send(&command)?;
+            verify_echo(response.dev_addr, Some(dev_addr))?;
+            Ok(dev_addr)
+        }
+
+        /// Leave the multicast group
+        pub fn multicast_disable(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::MulticastDisable {};
+            let response = self.client.send(&command)?;
+            verify_echo(response.dev_addr, None)
+        }
+
+        pub fn lorawan_version(&mut self) -> Result<LoraVersion, LoraE5Error> {
+            let command = commands::LoraVersionGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.version)
+        }
+
+        pub fn lorawan_version_set(
+            &mut self,
+            version: LoraVersion,
+        ) -> Result<LoraVersion, LoraE5Error> {
+            let command = commands::LoraVersionSet {
+                version: version.clone(),
+            };
+            let response = self.client.send(&command)?;
+            verify_echo(&response.version, &version)?;
+            Ok(response.version)
+        }
+
+        /// Data rate the next uplink is sent at
+        pub fn current_data_rate(&mut self) -> Result<DataRate, LoraE5Error> {
+            let command = commands::LoraCurrentDataRateGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.data_rate)
+        }
+
+        /// Battery level reported in DevStatusAns: 0 is external power, 1 to 254 the level and
+        /// 255 unknown
+        pub fn battery_level(&mut self) -> Result<u8, LoraE5Error> {
+            let command = commands::LoraBatteryLevelGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.level)
+        }
+
+        pub fn battery_level_set(&mut self, level: u8) -> Result<u8, LoraE5Error> {
+            let command = commands::LoraBatteryLevelSet { level };
+            let response = self.client.send(&command)?;
+            verify_echo(response.level, level)?;
+            Ok(response.level)
+        }
+
+        pub fn downlink_message_count(&self) -> Result<u32, LoraE5Error> {
+            Ok(self
+                .state
+                .message_received_count
+                .try_signaled_value()
+                .unwrap_or_default())
+        }
+
+        pub fn tx_power_force_set(&mut self, db_m: u8) -> Result<u8, LoraE5Error> {
+            let command = commands::TxPowerForceSet::new(db_m);
+            let response = self.client.send(&command)?;
+            Ok(response.db_m)
+        }
+
+        pub fn tx_power_table(&mut self) -> Result<Vec<u8, 12>, LoraE5Error> {
+            let command = commands::TxPowerTableGet::default();
+            let response = self.client.send(&command)?;
+            Ok(response.db_m_list()?)
         }
     }
 }
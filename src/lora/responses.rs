@@ -1,4 +1,8 @@
-use crate::lora::types::{LoraJoinMode, LoraJoiningStartingStatus, LoraJoiningStatus};
+use crate::lora::types::{
+    BeaconChannel, BeaconStatus, DataRate, DataRateInfo, LoraChannel, LoraJoinMode,
+    LoraJoiningStartingStatus, LoraJoiningStatus, LoraVersion, ReceiveDelay, ReceiveDelays,
+    Rx1Channel, Rx2Window,
+};
 use atat_derive::AtatResp;
 use core::str::FromStr;
 #[cfg(feature = "debug")]
@@ -21,7 +25,6 @@ impl ModeGetSetResponse {
 /// ID ABP DevAddr Get/Set Response
 #[derive(Debug, Clone, AtatResp, PartialEq)]
 pub struct AbpDevAddrResponse {
-    pub dev_addr_text: String<14>,
     pub dev_addr: HexStr<u32>,
 }
 
@@ -62,17 +65,26 @@ pub struct DataRateGetSetResponse {
     pub rate: String<42>,
 }
 
+impl DataRateGetSetResponse {
+    pub fn info(&self) -> Result<DataRateInfo, atat::Error> {
+        DataRateInfo::from_str(self.rate.as_str()).map_err(|_| {
+            #[cfg(feature = "debug")]
+            error!("Could not parse data rate");
+            atat::Error::Parse
+        })
+    }
+}
+
 /// LoRaWAN class get/set response
 #[derive(Debug, Clone, AtatResp, PartialEq)]
 pub struct LoRaWANClassGetSetResponse {
     pub class: String<2>,
 }
 
-/// AppKey Set response
+/// KEY Set response, the key as echoed by the module
 #[derive(Debug, Clone, AtatResp, PartialEq)]
-pub struct AppKeySetResponse {
-    // APPKEY <32 char> = 41 char = 82 bytes
-    pub response: String<82>,
+pub struct KeySetResponse {
+    pub key: HexStr<u128>,
 }
 
 /// Join response
@@ -135,10 +147,10 @@ impl TxPowerTable {
                 error!("Could not parse u8");
                 atat::Error::Parse
             })?)
-            .map_err(|e| {
+            .map_err(|_e| {
                 #[cfg(feature = "debug")]
                 {
-                    error!("Could not add u8 to return of tx power tables: {}", e);
+                    error!("Could not add u8 to return of tx power tables: {}", _e);
                 }
                 atat::Error::Parse
             })?;
@@ -183,3 +195,163 @@ impl UplinkDownlinkCounterGetResponse {
         self.downlink
     }
 }
+
+/// LW DC/JDC/NET get and set response
+/// Example return `DC, ON`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoraWanSwitchResponse {
+    pub on: bool,
+}
+
+impl atat::AtatResp for LoraWanSwitchResponse {}
+
+/// LW VER get and set response
+/// Example return `VER, V102B`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoraVersionGetSetResponse {
+    pub version: LoraVersion,
+}
+
+impl atat::AtatResp for LoraVersionGetSetResponse {}
+
+/// LW CDR response
+/// Example return `CDR, DR5`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentDataRateResponse {
+    pub data_rate: DataRate,
+}
+
+impl atat::AtatResp for CurrentDataRateResponse {}
+
+/// LW BAT get and set response
+/// Example return `BAT, 254`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryLevelGetSetResponse {
+    pub level: u8,
+}
+
+impl atat::AtatResp for BatteryLevelGetSetResponse {}
+
+/// LW MC get/set response
+/// Example return `MC, ON, 01234567` or `MC, OFF`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MulticastResponse {
+    /// DevAddr of the multicast group, `None` if multicast is off
+    pub dev_addr: Option<u32>,
+}
+
+impl atat::AtatResp for MulticastResponse {}
+
+/// Most channels a channel plan can have (US915/AU915: 64 + 8)
+pub const MAX_CHANNELS: usize = 72;
+
+/// CH get response
+/// Example return `3; 0,868100000,DR0,DR5; 1,868300000,DR0,DR5; 2,868500000,DR0,DR5`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelsGetResponse {
+    pub channels: Vec<LoraChannel, MAX_CHANNELS>,
+}
+
+impl atat::AtatResp for ChannelsGetResponse {}
+
+/// CH set response
+/// Example return `3,867100000,DR0,DR5`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelSetResponse {
+    pub channel: LoraChannel,
+}
+
+impl atat::AtatResp for ChannelSetResponse {}
+
+/// CH on/off set response, the channel and whether it is now enabled
+/// Example return `CH2 OFF`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelEnableSetResponse {
+    pub index: u8,
+    pub on: bool,
+}
+
+impl atat::AtatResp for ChannelEnableSetResponse {}
+
+/// CH NUM set response, the enabled channel range
+/// Example return `NUM, 8-15`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelNumSetResponse {
+    pub first: u8,
+    pub last: u8,
+}
+
+impl atat::AtatResp for ChannelNumSetResponse {}
+
+/// RXWIN1 get response
+/// Example return `ON; 8; 0,923300000; 1,923900000; ...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rx1ChannelsGetResponse {
+    pub channels: Vec<Rx1Channel, 8>,
+}
+
+impl atat::AtatResp for Rx1ChannelsGetResponse {}
+
+/// RXWIN1 set response
+/// Example return `0,923300000`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rx1ChannelSetResponse {
+    pub channel: Rx1Channel,
+}
+
+impl atat::AtatResp for Rx1ChannelSetResponse {}
+
+/// RXWIN2 get/set response
+/// Example return `869.525000,DR3`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rx2WindowGetSetResponse {
+    pub window: Rx2Window,
+}
+
+impl atat::AtatResp for Rx2WindowGetSetResponse {}
+
+/// BEACON get/set response
+/// Example return `869525000,DR3`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconGetSetResponse {
+    pub channel: BeaconChannel,
+}
+
+impl atat::AtatResp for BeaconGetSetResponse {}
+
+/// BEACON STATUS response
+/// Example return `STATUS, LOCKED`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconStatusResponse {
+    pub status: BeaconStatus,
+}
+
+impl atat::AtatResp for BeaconStatusResponse {}
+
+/// BEACON PERIOD get/set response
+/// Example return `PERIOD, 7`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingSlotPeriodicityResponse {
+    pub periodicity: u8,
+}
+
+impl atat::AtatResp for PingSlotPeriodicityResponse {}
+
+/// DELAY get response
+/// Example return `RX1, 1000; RX2, 2000; JRX1, 5000; JRX2, 6000`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiveDelaysGetResponse {
+    pub delays: ReceiveDelays,
+}
+
+impl atat::AtatResp for ReceiveDelaysGetResponse {}
+
+/// DELAY set response
+/// Example return `RX1, 5000`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiveDelaySetResponse {
+    pub delay: ReceiveDelay,
+    pub ms: u16,
+}
+
+impl atat::AtatResp for ReceiveDelaySetResponse {}
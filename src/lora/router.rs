@@ -0,0 +1,88 @@
+//! # Downlink routing by FPort
+//!
+//! A [DownlinkRouter] hands each received downlink to the handler registered for its port, or
+//! to the default handler when no route matches.
+//!
+//! ```
+//! use seeed_lora_e5_at_commands::lora::router::DownlinkRouter;
+//! use seeed_lora_e5_at_commands::urc::MessageStats;
+//!
+//! let mut on_command = |_port: u8, _payload: &[u8], _stats: &MessageStats| {};
+//! let mut on_ota_chunk = |_port: u8, _payload: &[u8], _stats: &MessageStats| {};
+//! let mut router: DownlinkRouter<'_, 4> = DownlinkRouter::new();
+//! router.route(1, &mut on_command).ok();
+//! router.route_range(20..=29, &mut on_ota_chunk).ok();
+//! ```
+
+use crate::urc::{MessageStats, ReceivedMessage};
+use core::ops::RangeInclusive;
+use heapless::Vec;
+
+/// Called with the port, payload and RX window stats of a downlink
+pub type DownlinkHandler<'h> = &'h mut dyn FnMut(u8, &[u8], &MessageStats);
+
+/// Dispatches downlinks to handlers per FPort (range), with up to `ROUTES` routes.
+/// Routes are matched in the order they were added.
+pub struct DownlinkRouter<'h, const ROUTES: usize> {
+    routes: Vec<(RangeInclusive<u8>, DownlinkHandler<'h>), ROUTES>,
+    default: Option<DownlinkHandler<'h>>,
+}
+
+impl<const ROUTES: usize> Default for DownlinkRouter<'_, ROUTES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'h, const ROUTES: usize> DownlinkRouter<'h, ROUTES> {
+    pub const fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            default: None,
+        }
+    }
+
+    /// Handle downlinks on `port`. Gives the handler back if all routes are taken.
+    pub fn route(
+        &mut self,
+        port: u8,
+        handler: DownlinkHandler<'h>,
+    ) -> Result<(), DownlinkHandler<'h>> {
+        self.route_range(port..=port, handler)
+    }
+
+    /// Handle downlinks on any port in `ports`. Gives the handler back if all routes are taken.
+    pub fn route_range(
+        &mut self,
+        ports: RangeInclusive<u8>,
+        handler: DownlinkHandler<'h>,
+    ) -> Result<(), DownlinkHandler<'h>> {
+        self.routes
+            .push((ports, handler))
+            .map_err(|(_, handler)| handler)
+    }
+
+    /// Handle downlinks on ports that have no route
+    pub fn default_route(&mut self, handler: DownlinkHandler<'h>) {
+        self.default = Some(handler);
+    }
+
+    /// Call the handler for the downlink's port.
+    /// Returns `false` if no route matched and there is no default handler.
+    pub fn dispatch(&mut self, message: &ReceivedMessage, stats: &MessageStats) -> bool {
+        let payload = &message.payload[..message.length];
+        let handler = self
+            .routes
+            .iter_mut()
+            .find(|(ports, _)| ports.contains(&message.port))
+            .map(|(_, handler)| handler)
+            .or(self.default.as_mut());
+        match handler {
+            Some(handler) => {
+                handler(message.port, payload, stats);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -161,3 +161,387 @@ pub enum LoraVersion {
     V102Alpha,
     V11,
 }
+
+impl LoraVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoraVersion::V10 => "V100",
+            LoraVersion::V101 => "V101",
+            LoraVersion::V102 => "V102",
+            LoraVersion::V102B => "V102B",
+            LoraVersion::V103 => "V103",
+            LoraVersion::V102Alpha => "V102A",
+            LoraVersion::V11 => "V110",
+        }
+    }
+}
+
+/// Parses both `V102B` and `V1.0.2B`
+impl FromStr for LoraVersion {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut version: String<8> = String::new();
+        for c in value.trim().chars().filter(|c| *c != '.') {
+            version.push(c.to_ascii_uppercase()).map_err(|_| ())?;
+        }
+        match version.as_str() {
+            "V10" | "V100" => Ok(LoraVersion::V10),
+            "V101" => Ok(LoraVersion::V101),
+            "V102" => Ok(LoraVersion::V102),
+            "V102B" => Ok(LoraVersion::V102B),
+            "V103" => Ok(LoraVersion::V103),
+            "V102A" | "V102ALPHA" => Ok(LoraVersion::V102Alpha),
+            "V11" | "V110" => Ok(LoraVersion::V11),
+            _ => Err(()),
+        }
+    }
+}
+
+/// On/off settings of the `AT+LW` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoraWanSwitch {
+    /// Duty cycle limitation (`DC`)
+    DutyCycle,
+    /// Join duty cycle limitation (`JDC`)
+    JoinDutyCycle,
+    /// Public network sync word, off for a private network (`NET`)
+    PublicNetwork,
+}
+
+impl LoraWanSwitch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LoraWanSwitch::DutyCycle => "DC",
+            LoraWanSwitch::JoinDutyCycle => "JDC",
+            LoraWanSwitch::PublicNetwork => "NET",
+        }
+    }
+}
+
+/// Data rate as reported by `AT+DR`, e.g. `US915 DR0 SF10 BW125K` or `EU868 DR7 FSK`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRateInfo {
+    pub region: LoraRegion,
+    pub dr: u8,
+    /// LoRa spreading factor, `None` for FSK
+    pub spreading_factor: Option<u8>,
+    /// LoRa bandwidth in kHz, `None` for FSK
+    pub bandwidth_khz: Option<u16>,
+    pub fsk: bool,
+}
+
+impl FromStr for DataRateInfo {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split_whitespace();
+        let region = LoraRegion::from_str(parts.next().ok_or(())?)?;
+        let dr = parts
+            .next()
+            .and_then(|dr| dr.strip_prefix("DR"))
+            .and_then(|dr| dr.parse().ok())
+            .ok_or(())?;
+        let mut info = DataRateInfo {
+            region,
+            dr,
+            spreading_factor: None,
+            bandwidth_khz: None,
+            fsk: false,
+        };
+        for part in parts {
+            if part == "FSK" {
+                info.fsk = true;
+            } else if let Some(sf) = part.strip_prefix("SF") {
+                info.spreading_factor = Some(sf.parse().map_err(|_| ())?);
+            } else if let Some(bw) = part.strip_prefix("BW") {
+                let bw = bw.trim_end_matches('K');
+                info.bandwidth_khz = Some(bw.parse().map_err(|_| ())?);
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// Channel of the channel plan, e.g. `0,868100000,DR0,DR5` as listed by `AT+CH`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoraChannel {
+    pub index: u8,
+    pub frequency_hz: u32,
+    pub dr_min: u8,
+    pub dr_max: u8,
+}
+
+impl FromStr for LoraChannel {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split(',').map(str::trim);
+        let index = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let frequency_hz = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let mut data_rates = parts.map(|dr| {
+            let dr = dr.strip_prefix("DR").unwrap_or(dr);
+            let end = dr.find(|c: char| !c.is_ascii_digit()).unwrap_or(dr.len());
+            dr[..end].parse::<u8>().map_err(|_| ())
+        });
+        let dr_min = data_rates.next().ok_or(())??;
+        let dr_max = data_rates.next().ok_or(())??;
+        Ok(LoraChannel {
+            index,
+            frequency_hz,
+            dr_min,
+            dr_max,
+        })
+    }
+}
+
+/// LoRaWAN data rate, DR0 to DR15
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataRate(u8);
+
+impl DataRate {
+    pub fn value(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for DataRate {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value <= 15 {
+            Ok(Self(value))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl FromStr for DataRate {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let value = value.strip_prefix("DR").unwrap_or(value);
+        Self::try_from(value.parse::<u8>().map_err(|_| ())?)
+    }
+}
+
+/// Parse a frequency given either in MHz (`869.525`) or in Hz (`869525000`)
+pub(crate) fn parse_frequency_hz(value: &str) -> Result<u32, ()> {
+    let value = value.trim();
+    match value.split_once('.') {
+        Some((mhz, fraction)) => {
+            let mhz: u32 = mhz.parse().map_err(|_| ())?;
+            let mut hz = 0;
+            for (index, digit) in fraction.chars().take(6).enumerate() {
+                let digit = digit.to_digit(10).ok_or(())?;
+                hz += digit * 10u32.pow(5 - index as u32);
+            }
+            mhz.checked_mul(1_000_000)
+                .and_then(|mhz| mhz.checked_add(hz))
+                .ok_or(())
+        }
+        None => {
+            let value: u32 = value.parse().map_err(|_| ())?;
+            if value < 10_000 {
+                value.checked_mul(1_000_000).ok_or(())
+            } else {
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// RX2 window frequency and data rate, e.g. `869525000,DR3` as reported by `AT+RXWIN2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rx2Window {
+    pub frequency_hz: u32,
+    pub data_rate: DataRate,
+}
+
+impl FromStr for Rx2Window {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (frequency, data_rate) = value.split_once(',').ok_or(())?;
+        Ok(Rx2Window {
+            frequency_hz: parse_frequency_hz(frequency)?,
+            data_rate: DataRate::from_str(data_rate)?,
+        })
+    }
+}
+
+/// Custom RX1 channel frequency, e.g. `0,923300000` as reported by `AT+RXWIN1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rx1Channel {
+    pub index: u8,
+    pub frequency_hz: u32,
+}
+
+impl FromStr for Rx1Channel {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (index, frequency) = value.split_once(',').ok_or(())?;
+        Ok(Rx1Channel {
+            index: index.trim().parse().map_err(|_| ())?,
+            frequency_hz: parse_frequency_hz(frequency)?,
+        })
+    }
+}
+
+/// One of the receive delays of `AT+DELAY`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiveDelay {
+    /// Delay before the RX1 window
+    Rx1,
+    /// Delay before the RX2 window
+    Rx2,
+    /// Delay before the RX1 window of a join accept
+    JoinRx1,
+    /// Delay before the RX2 window of a join accept
+    JoinRx2,
+}
+
+impl ReceiveDelay {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReceiveDelay::Rx1 => "RX1",
+            ReceiveDelay::Rx2 => "RX2",
+            ReceiveDelay::JoinRx1 => "JRX1",
+            ReceiveDelay::JoinRx2 => "JRX2",
+        }
+    }
+}
+
+impl FromStr for ReceiveDelay {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "RX1" => Ok(ReceiveDelay::Rx1),
+            "RX2" => Ok(ReceiveDelay::Rx2),
+            "JRX1" => Ok(ReceiveDelay::JoinRx1),
+            "JRX2" => Ok(ReceiveDelay::JoinRx2),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Parse a single `RX1, 1000` delay
+pub(crate) fn parse_receive_delay(value: &str) -> Result<(ReceiveDelay, u16), ()> {
+    let (delay, ms) = value.split_once(',').ok_or(())?;
+    Ok((
+        ReceiveDelay::from_str(delay)?,
+        ms.trim().parse().map_err(|_| ())?,
+    ))
+}
+
+/// Receive window delays in milliseconds, e.g. `RX1, 1000; RX2, 2000; JRX1, 5000; JRX2, 6000`
+/// as reported by `AT+DELAY`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiveDelays {
+    pub rx1_ms: u16,
+    pub rx2_ms: u16,
+    pub join_rx1_ms: u16,
+    pub join_rx2_ms: u16,
+}
+
+impl ReceiveDelays {
+    pub fn get(&self, delay: ReceiveDelay) -> u16 {
+        match delay {
+            ReceiveDelay::Rx1 => self.rx1_ms,
+            ReceiveDelay::Rx2 => self.rx2_ms,
+            ReceiveDelay::JoinRx1 => self.join_rx1_ms,
+            ReceiveDelay::JoinRx2 => self.join_rx2_ms,
+        }
+    }
+
+    /// Each RX2 window has to open after its RX1 window
+    pub fn is_valid(&self) -> bool {
+        self.rx1_ms < self.rx2_ms && self.join_rx1_ms < self.join_rx2_ms
+    }
+}
+
+impl FromStr for ReceiveDelays {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (mut rx1_ms, mut rx2_ms, mut join_rx1_ms, mut join_rx2_ms) = (None, None, None, None);
+        for delay in value.split(';') {
+            let (delay, ms) = parse_receive_delay(delay)?;
+            let slot = match delay {
+                ReceiveDelay::Rx1 => &mut rx1_ms,
+                ReceiveDelay::Rx2 => &mut rx2_ms,
+                ReceiveDelay::JoinRx1 => &mut join_rx1_ms,
+                ReceiveDelay::JoinRx2 => &mut join_rx2_ms,
+            };
+            *slot = Some(ms);
+        }
+        Ok(ReceiveDelays {
+            rx1_ms: rx1_ms.ok_or(())?,
+            rx2_ms: rx2_ms.ok_or(())?,
+            join_rx1_ms: join_rx1_ms.ok_or(())?,
+            join_rx2_ms: join_rx2_ms.ok_or(())?,
+        })
+    }
+}
+
+/// Class B beacon frequency and data rate, e.g. `869525000,DR3` as reported by `AT+BEACON`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeaconChannel {
+    pub frequency_hz: u32,
+    pub data_rate: DataRate,
+}
+
+impl FromStr for BeaconChannel {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (frequency, data_rate) = value.split_once(',').ok_or(())?;
+        Ok(BeaconChannel {
+            frequency_hz: parse_frequency_hz(frequency)?,
+            data_rate: DataRate::from_str(data_rate)?,
+        })
+    }
+}
+
+/// Class B beacon acquisition state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeaconStatus {
+    /// Not in Class B
+    Idle,
+    /// Looking for the beacon after switching to Class B
+    Searching,
+    /// Beacon found, ping slots are open
+    Locked,
+    /// Beacon missed for too long, the module falls back to Class A
+    Lost,
+    /// No beacon found
+    Failed,
+}
+
+impl FromStr for BeaconStatus {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "IDLE" => Ok(BeaconStatus::Idle),
+            "SEARCHING" => Ok(BeaconStatus::Searching),
+            "LOCKED" => Ok(BeaconStatus::Locked),
+            "LOST" => Ok(BeaconStatus::Lost),
+            "FAILED" => Ok(BeaconStatus::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Highest data rate `AT+DR` takes (`DR15`)
+pub const MAX_DATA_RATE: u8 = 15;
+
+/// Largest ping slot periodicity; ping slots open every `2^periodicity` seconds
+pub const MAX_PING_SLOT_PERIODICITY: u8 = 7;
+
+/// Longest uplink payload `AT+MSGHEX`/`AT+CMSGHEX` take, whatever the data rate allows
+pub const MAX_UPLINK_LEN: usize = 242;
+
+/// Answer to a link check, with the RX window stats of the downlink carrying it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkCheckResult {
+    /// Demodulation margin of the uplink at the best gateway, in dB
+    pub margin_db: u8,
+    /// Number of gateways that received the uplink
+    pub gateway_count: u8,
+    pub rssi: i8,
+    pub snr: f32,
+}
@@ -1,12 +1,11 @@
-use crate::client::asynch::JoinStatus;
-use crate::urc::{
-    MessageStats, ReceivedMessage, URCMessages, LAST_LORA_MESSAGE_RECEIVED, LORA_JOIN_STATUS,
-    LORA_MESSAGE_RECEIVED_COUNT, LORA_MESSAGE_RECEIVED_STATS,
-};
+use crate::error::LoraE5Error;
+use crate::lora::types::BeaconStatus;
+use crate::urc::URCMessages;
 use atat::digest::ParseError;
 #[cfg(feature = "debug")]
 use atat::helpers::LossyStr;
-use atat::nom::{branch, bytes, character, sequence};
+use atat::nom::{branch, bytes, sequence};
+use core::str::FromStr;
 #[cfg(feature = "debug")]
 use defmt::{debug, error, trace};
 use heapless::String;
@@ -47,7 +46,7 @@ impl JoinUrc {
             trace!("+JOIN PARSE: {}", v);
         }
 
-        let ret = match core::str::from_utf8(val) {
+        match core::str::from_utf8(val) {
             Ok(val) => match val {
                 x if x.starts_with("Start") => Ok(JoinUrc::Start),
                 x if x.starts_with("Auto-Join") => Ok(JoinUrc::AutoJoin(AutoJoin::Off)),
@@ -69,26 +68,21 @@ impl JoinUrc {
                 _ => Err(ParseError::NoMatch),
             },
             _ => Err(ParseError::NoMatch),
-        };
-        match &ret {
-            Ok(JoinUrc::Start | JoinUrc::Failed) => LORA_JOIN_STATUS.signal(JoinStatus::Joining),
-            Ok(JoinUrc::NetworkJoined | JoinUrc::JoinedAlready | JoinUrc::Success(_, _)) => {
-                LORA_JOIN_STATUS.signal(JoinStatus::Success)
-            }
-            _ => {}
         }
-        ret
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum MessageHexSend {
     Start,
     Pending,
     AckReceived,
     WaitAck,
     RxWinRssiSnr(u8, i8, f32),
+    Payload(Payload),
     Done,
+    Error(LoraE5Error),
 }
 
 impl From<MessageHexSend> for URCMessages {
@@ -113,43 +107,106 @@ impl MessageHexSend {
             x if x.starts_with(b"ACK Received") => Ok(MessageHexSend::AckReceived),
             x if x.starts_with(b"Wait ACK") => Ok(MessageHexSend::WaitAck),
             x if x.starts_with(b"FPENDING") => Ok(MessageHexSend::Pending),
+            x if x.starts_with(b"PORT: ") => Ok(MessageHexSend::Payload(Payload::parse(x)?)),
             x if x.starts_with(b"RXWIN") => {
-                let (_, (_, rxwin, _, rssi, _, snr)) = sequence::tuple((
-                    bytes::streaming::tag(b"RXWIN"),
-                    bytes::streaming::take_until(","),
-                    bytes::streaming::tag(b", RSSI "),
-                    bytes::streaming::take_until(","),
-                    bytes::streaming::tag(b", SNR "),
-                    bytes::streaming::take_while(character::is_alphanumeric),
-                ))(x)?;
-                let rxwin = core::str::from_utf8(rxwin).map_err(|_| ParseError::NoMatch)?;
-                let rssi = core::str::from_utf8(rssi).map_err(|_| ParseError::NoMatch)?;
-                let snr = core::str::from_utf8(snr).map_err(|_| ParseError::NoMatch)?;
-                #[cfg(feature = "debug")]
-                trace!("rxwin: {}, rssi: {}, snr: {}", rxwin, rssi, snr);
-                let rxwin = rxwin.parse().unwrap_or(0);
-                let rssi = rssi.parse().unwrap_or(0);
-                let snr = snr.parse().unwrap_or(0.0);
+                let (rxwin, rssi, snr) = parse_rx_win_rssi_snr(x)?;
                 Ok(MessageHexSend::RxWinRssiSnr(rxwin, rssi, snr))
             }
             x if x.starts_with(b"Done") => Ok(MessageHexSend::Done),
-            _ => Err(ParseError::NoMatch),
+            x => LoraE5Error::parse(x)
+                .map(MessageHexSend::Error)
+                .ok_or(ParseError::NoMatch),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Payload {
+    /// Sent to the multicast group rather than to this device
+    pub multicast: bool,
     pub port: u8,
+    pub payload: [u8; 243],
     pub length: usize,
 }
 
+impl Payload {
+    /// Parse `PORT: <port>; RX: "<hex payload>"`, prefixed with `MC, ` for multicast downlinks
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, ParseError> {
+        let multicast = buf.starts_with(b"MC, ");
+        let buf = if multicast { &buf[4..] } else { buf };
+        let (_, (_, port, _, payload_str, _)) = sequence::tuple((
+            bytes::streaming::tag("PORT: "),
+            bytes::streaming::take_until(";"),
+            bytes::streaming::tag("; RX: \""),
+            bytes::streaming::take_until("\""),
+            bytes::streaming::tag("\""),
+        ))(buf)
+        .inspect_err(|_| {
+            #[cfg(feature = "debug")]
+            error!("Error on PORT parse");
+        })?;
+        let payload_str_len = payload_str.len();
+        #[cfg(feature = "debug")]
+        debug!("Payload str [{}]{}", payload_str_len, LossyStr(payload_str));
+        let length = payload_str_len / 2 + if payload_str_len % 2 != 0 { 1 } else { 0 };
+        let mut payload = [0u8; 243];
+
+        for (index, val) in payload_str.iter().enumerate().take(payload_str_len) {
+            let val_bytes = [*val];
+            let val_bytes = core::str::from_utf8(&val_bytes).map_err(|_| ParseError::NoMatch)?;
+            let val = u8::from_str_radix(val_bytes, 16).map_err(|_| ParseError::NoMatch)?;
+            let val = if index % 2 == 0 { val << 4 } else { val };
+            payload[index / 2] += val;
+        }
+
+        let port = core::str::from_utf8(port)
+            .map_err(|_| ParseError::NoMatch)?
+            .parse()
+            .map_err(|_| ParseError::NoMatch)?;
+
+        Ok(Payload {
+            multicast,
+            port,
+            payload,
+            length,
+        })
+    }
+}
+
+/// Parse `RXWIN<n>, RSSI <rssi>, SNR <snr>`
+fn parse_rx_win_rssi_snr(buf: &[u8]) -> Result<(u8, i8, f32), ParseError> {
+    let (_, (_, rxwin, _, rssi, _, snr)) = sequence::tuple((
+        bytes::streaming::tag(b"RXWIN"),
+        bytes::streaming::take_until(","),
+        bytes::streaming::tag(b", RSSI "),
+        bytes::streaming::take_until(","),
+        bytes::streaming::tag(b", SNR "),
+        bytes::complete::take_while(|c: u8| c == b'-' || c == b'.' || c.is_ascii_digit()),
+    ))(buf)?;
+
+    let rxwin = core::str::from_utf8(rxwin).map_err(|_| ParseError::NoMatch)?;
+    let rssi = core::str::from_utf8(rssi).map_err(|_| ParseError::NoMatch)?;
+    let snr = core::str::from_utf8(snr).map_err(|_| ParseError::NoMatch)?;
+    #[cfg(feature = "debug")]
+    trace!("rxwin: {}, rssi: {}, snr: {}", rxwin, rssi, snr);
+    let rxwin = rxwin.parse().map_err(|_| ParseError::NoMatch)?;
+    let rssi = rssi.parse().map_err(|_| ParseError::NoMatch)?;
+    let snr = snr.parse().map_err(|_| ParseError::NoMatch)?;
+    Ok((rxwin, rssi, snr))
+}
+
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum MessageReceived {
+    /// Uplink of an `AT+MSG` (e.g. a link check) started
+    Start,
     Payload(Payload),
     RxWinRssiSnr(u8, i8, f32),
     FPending,
+    /// `Link <margin>, <gateway count>`, the answer to a link check
+    Link(u8, u8),
     Done,
+    Error(LoraE5Error),
 }
 
 impl From<MessageReceived> for URCMessages {
@@ -168,86 +225,65 @@ impl MessageReceived {
             trace!("+MSG PARSE: {}", v);
         }
         match val {
-            x if x.starts_with(b"PORT: ") => {
-                let (_, (_, port, _, payload_str, _)) = sequence::tuple((
-                    bytes::streaming::tag("PORT: "),
-                    bytes::streaming::take_until(";"),
-                    bytes::streaming::tag("; RX: \""),
-                    bytes::streaming::take_until("\""),
-                    bytes::streaming::tag("\""),
-                ))(x)
-                .map_err(|e| {
-                    #[cfg(feature = "debug")]
-                    error!("Error on +MSG Port parse");
-                    e
-                })?;
-                let payload_str_len = payload_str.len();
-                #[cfg(feature = "debug")]
-                debug!("Payload str [{}]{}", payload_str_len, LossyStr(payload_str));
-                let length = payload_str_len / 2 + if payload_str_len % 2 != 0 { 1 } else { 0 };
-                let mut payload = [0u8; 243];
-
-                for (index, val) in payload_str.iter().enumerate().take(payload_str_len) {
-                    let val_bytes = [*val];
-                    let val_bytes =
-                        core::str::from_utf8(&val_bytes).map_err(|_| ParseError::NoMatch)?;
-                    let val = u8::from_str_radix(val_bytes, 16).map_err(|_| ParseError::NoMatch)?;
-                    let val = if index % 2 == 0 { val << 4 } else { val };
-                    payload[index / 2] += val;
-                }
-
-                let port = core::str::from_utf8(port)
-                    .map_err(|_| ParseError::NoMatch)?
-                    .parse()
-                    .map_err(|_| ParseError::NoMatch)?;
-                LORA_MESSAGE_RECEIVED_STATS.reset();
-
-                LAST_LORA_MESSAGE_RECEIVED.signal(ReceivedMessage {
-                    payload,
-                    length,
-                    port,
-                });
-
-                let count = match LORA_MESSAGE_RECEIVED_COUNT.try_signaled_value() {
-                    Some(v) => {
-                        if v == u32::MAX {
-                            0
-                        } else {
-                            v + 1
-                        }
-                    }
-                    None => 1,
-                };
-                LORA_MESSAGE_RECEIVED_COUNT.signal(count);
-
-                Ok(MessageReceived::Payload(Payload { length, port }))
+            x if x.starts_with(b"PORT: ") || x.starts_with(b"MC, PORT: ") => {
+                Ok(MessageReceived::Payload(Payload::parse(x)?))
             }
             x if x.starts_with(b"RXWIN") => {
-                let (_, (_, rxwin, _, rssi, _, snr)) = sequence::tuple((
-                    bytes::streaming::tag(b"RXWIN"),
-                    bytes::streaming::take_until(","),
-                    bytes::streaming::tag(b", RSSI "),
-                    bytes::streaming::take_until(","),
-                    bytes::streaming::tag(b", SNR "),
-                    bytes::streaming::take_while(character::is_alphanumeric),
-                ))(x)?;
-
-                let rxwin = core::str::from_utf8(rxwin).map_err(|_| ParseError::NoMatch)?;
-                let rssi = core::str::from_utf8(rssi).map_err(|_| ParseError::NoMatch)?;
-                let snr = core::str::from_utf8(snr).map_err(|_| ParseError::NoMatch)?;
-                #[cfg(feature = "debug")]
-                trace!("rxwin: {}, rssi: {}, snr: {}", rxwin, rssi, snr);
-                let rxwin = rxwin.parse().map_err(|_| ParseError::NoMatch)?;
-                let rssi = rssi.parse().map_err(|_| ParseError::NoMatch)?;
-                let snr = snr.parse().map_err(|_| ParseError::NoMatch)?;
-
-                LORA_MESSAGE_RECEIVED_STATS.signal(MessageStats { rxwin, rssi, snr });
-
+                let (rxwin, rssi, snr) = parse_rx_win_rssi_snr(x)?;
                 Ok(MessageReceived::RxWinRssiSnr(rxwin, rssi, snr))
             }
             x if x.starts_with(b"Done") => Ok(MessageReceived::Done),
             x if x.starts_with(b"FPENDING") => Ok(MessageReceived::FPending),
-            _ => Err(ParseError::NoMatch),
+            x if x.starts_with(b"Start") => Ok(MessageReceived::Start),
+            x if x.starts_with(b"Link ") => {
+                let link = core::str::from_utf8(&x[5..]).map_err(|_| ParseError::NoMatch)?;
+                let (margin, gateways) = link.split_once(',').ok_or(ParseError::NoMatch)?;
+                Ok(MessageReceived::Link(
+                    margin.trim().parse().map_err(|_| ParseError::NoMatch)?,
+                    gateways.trim().parse().map_err(|_| ParseError::NoMatch)?,
+                ))
+            }
+            x => LoraE5Error::parse(x)
+                .map(MessageReceived::Error)
+                .ok_or(ParseError::NoMatch),
+        }
+    }
+}
+
+/// Class B URCs, sent while acquiring and tracking the beacon
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BeaconUrc {
+    /// `SEARCHING`, `LOCKED`, `LOST` or `FAILED`
+    Status(BeaconStatus),
+    /// `PINGSLOT, <periodicity>`, the ping slot periodicity in use
+    PingSlotPeriodicity(u8),
+}
+
+impl From<BeaconUrc> for URCMessages {
+    fn from(value: BeaconUrc) -> Self {
+        Self::Beacon(value)
+    }
+}
+
+impl BeaconUrc {
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, ParseError> {
+        let (val, _) = sequence::tuple((bytes::streaming::tag("+BEACON: "),))(buf)?;
+
+        #[cfg(feature = "debug")]
+        {
+            let v = LossyStr(val);
+            trace!("+BEACON PARSE: {}", v);
+        }
+        let val = core::str::from_utf8(val).map_err(|_| ParseError::NoMatch)?;
+        match val.strip_prefix("PINGSLOT,") {
+            Some(periodicity) => periodicity
+                .trim()
+                .parse()
+                .map(BeaconUrc::PingSlotPeriodicity)
+                .map_err(|_| ParseError::NoMatch),
+            None => BeaconStatus::from_str(val)
+                .map(BeaconUrc::Status)
+                .map_err(|_| ParseError::NoMatch),
         }
     }
 }
@@ -0,0 +1,1066 @@
+//! # LoRa-E5 simulator
+//!
+//! Host side mock of a LoRa-E5 module, for running [SeeedLoraE5Client](crate::client::asynch::SeeedLoraE5Client)
+//! end-to-end without hardware. Implements [embedded_io_async::Read] and [embedded_io_async::Write]
+//! (and their blocking [embedded_io] counterparts), answers the AT commands like the E5 firmware
+//! does and emits its `+JOIN`, `+MSGHEX`/`+CMSGHEX` and `+MSG` URC sequences.
+//!
+//! The simulator is cheaply cloneable; all clones share the same modem, so one clone can be handed
+//! to the atat client as writer and another to the ingress as reader.
+
+use crate::error::LoraE5Error;
+use crate::test::types::RfConfig;
+use core::convert::Infallible;
+use core::fmt::Write as _;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+use std::collections::VecDeque;
+use std::string::String;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+/// A downlink the simulated network server has queued for the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedDownlink {
+    /// Sent to the multicast group, reported with a `MC, ` prefix
+    pub multicast: bool,
+    pub port: u8,
+    pub payload: Vec<u8>,
+    pub rssi: i8,
+    pub snr: f32,
+}
+
+impl SimulatedDownlink {
+    pub fn new(port: u8, payload: &[u8]) -> Self {
+        Self {
+            multicast: false,
+            port,
+            payload: payload.to_vec(),
+            rssi: -106,
+            snr: 4.0,
+        }
+    }
+}
+
+struct Modem {
+    mode: String,
+    dev_eui: u64,
+    app_eui: u64,
+    dev_addr: u32,
+    region: String,
+    data_rate: u8,
+    class: char,
+    adr: bool,
+    port: u8,
+    retry: u8,
+    repeat: u8,
+    joined: bool,
+    join_accept: bool,
+    /// The network server does not answer join requests at all
+    join_unanswered: bool,
+    ack: bool,
+    uplink_counter: u32,
+    downlink_counter: u32,
+    max_payload_len: u8,
+    duty_cycle: bool,
+    join_duty_cycle: bool,
+    public_network: bool,
+    lorawan_version: String,
+    battery_level: u8,
+    /// Margin and gateway count the network server answers link checks with, if it answers
+    link_check: Option<(u8, u8)>,
+    /// Multicast group DevAddr, if multicast is on
+    multicast: Option<u32>,
+    /// Index, frequency in Hz, DR range and whether the channel is enabled
+    channels: Vec<(u8, u32, u8, u8, bool)>,
+    rx1_channels: Vec<(u8, u32)>,
+    rx2: (u32, u8),
+    /// RX1, RX2, JRX1 and JRX2 delays
+    delays: [(&'static str, u16); 4],
+    test_rf: RfConfig,
+    /// Class B beacon frequency and data rate
+    beacon: (u32, u8),
+    beacon_status: &'static str,
+    ping_slot_periodicity: u8,
+    /// Receiving in TEST mode (`AT+TEST=RXLRPKT`)
+    test_rx: bool,
+    /// Sleeping, commands are ignored until woken by 0xFF bytes
+    asleep: bool,
+    /// End of a timed `AT+LOWPOWER=<ms>` sleep
+    wake_at: Option<Instant>,
+    /// `AT+LOWPOWER=AUTOON`, sleeps again after every command
+    auto_low_power: bool,
+    /// Hung, ignores everything until reset
+    wedged: bool,
+    /// Hangs in the middle of the next uplink
+    wedge_on_uplink: bool,
+    /// NRST is held low
+    held_in_reset: bool,
+    injected_errors: Vec<(String, String)>,
+    queued_downlinks: VecDeque<SimulatedDownlink>,
+    commands: Vec<String>,
+}
+
+impl Default for Modem {
+    fn default() -> Self {
+        Self {
+            mode: "LWOTAA".into(),
+            dev_eui: 0x2CF7_F120_2420_0001,
+            app_eui: 0x8000_0000_0000_0006,
+            dev_addr: 0x2601_1BDB,
+            region: "EU868".into(),
+            data_rate: 0,
+            class: 'A',
+            adr: true,
+            port: 8,
+            retry: 3,
+            repeat: 1,
+            joined: false,
+            join_accept: true,
+            join_unanswered: false,
+            ack: true,
+            uplink_counter: 0,
+            downlink_counter: 0,
+            max_payload_len: 51,
+            duty_cycle: true,
+            join_duty_cycle: true,
+            public_network: true,
+            lorawan_version: "V102B".into(),
+            battery_level: 255,
+            link_check: Some((20, 1)),
+            multicast: None,
+            channels: vec![
+                (0, 868_100_000, 0, 5, true),
+                (1, 868_300_000, 0, 5, true),
+                (2, 868_500_000, 0, 5, true),
+            ],
+            rx1_channels: vec![(0, 868_100_000), (1, 868_300_000), (2, 868_500_000)],
+            rx2: (869_525_000, 0),
+            delays: [("RX1", 1000), ("RX2", 2000), ("JRX1", 5000), ("JRX2", 6000)],
+            test_rf: RfConfig::default(),
+            beacon: (869_525_000, 3),
+            beacon_status: "IDLE",
+            ping_slot_periodicity: 7,
+            test_rx: false,
+            asleep: false,
+            wake_at: None,
+            auto_low_power: false,
+            wedged: false,
+            wedge_on_uplink: false,
+            held_in_reset: false,
+            injected_errors: Vec::new(),
+            queued_downlinks: VecDeque::new(),
+            commands: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    modem: Modem,
+    line: Vec<u8>,
+    rx: VecDeque<u8>,
+    waker: Option<Waker>,
+}
+
+impl Inner {
+    /// Boot as after `AT+RESET` or a hard reset, forgetting the runtime state
+    fn reboot(&mut self) {
+        let modem = &mut self.modem;
+        modem.joined = false;
+        modem.test_rx = false;
+        modem.asleep = false;
+        modem.wake_at = None;
+        modem.auto_low_power = false;
+        modem.wedged = false;
+        modem.beacon_status = "IDLE";
+        self.line.clear();
+        self.emit("+RESET: OK");
+        // The UART glitches while the module resets
+        self.rx.push_back(0);
+    }
+
+    /// End a timed sleep that has run out
+    fn wake_if_due(&mut self) {
+        if self.modem.wake_at.is_some_and(|at| Instant::now() >= at) {
+            self.modem.asleep = false;
+            self.modem.wake_at = None;
+            self.emit("+LOWPOWER: WAKEUP");
+        }
+    }
+
+    fn emit(&mut self, s: &str) {
+        self.rx.extend(s.as_bytes());
+        self.rx.extend(b"\r\n");
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Simulated LoRa-E5 module
+#[derive(Clone, Default)]
+pub struct LoraE5Simulator {
+    inner: Arc<(Mutex<Inner>, Condvar)>,
+}
+
+impl LoraE5Simulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut Inner) -> R) -> R {
+        let (lock, condvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+        inner.wake_if_due();
+        let ret = f(&mut inner);
+        if !inner.rx.is_empty() {
+            condvar.notify_all();
+        }
+        ret
+    }
+
+    /// Commands received from the host so far, without the trailing `\r\n`
+    pub fn commands(&self) -> Vec<String> {
+        self.with(|inner| inner.modem.commands.clone())
+    }
+
+    /// Answer the next command starting with `command` (e.g. `AT+MODE`) with `error` instead.
+    /// `ERROR(-n)` codes are answered as `+CMD: ERROR(-n)`, LoRaWAN failures as the
+    /// corresponding `+CMD: <message>` URC.
+    pub fn inject_error(&self, command: &str, error: LoraE5Error) {
+        let message = match (error.code(), &error) {
+            (Some(code), _) => format!("ERROR({})", code),
+            (None, LoraE5Error::NotJoined) => "Please join network first".into(),
+            (None, LoraE5Error::NoFreeChannel) => "No free channel".into(),
+            (None, LoraE5Error::LengthError) => "Length error 0".into(),
+            (None, LoraE5Error::DataRateError) => "DR error".into(),
+            (None, LoraE5Error::Busy) => "LoRaWAN modem is busy".into(),
+            _ => "ERROR(-24)".into(),
+        };
+        self.with(|inner| {
+            inner
+                .modem
+                .injected_errors
+                .push((command.to_uppercase(), message))
+        });
+    }
+
+    /// Whether the simulated network server accepts the next join request
+    pub fn set_join_accept(&self, accept: bool) {
+        self.with(|inner| inner.modem.join_accept = accept);
+    }
+
+    /// Whether the simulated network server ignores join requests, the module then ends the
+    /// join with a bare `+JOIN: Done`
+    pub fn set_join_unanswered(&self, unanswered: bool) {
+        self.with(|inner| inner.modem.join_unanswered = unanswered);
+    }
+
+    /// Whether the simulated network server acknowledges confirmed uplinks
+    pub fn set_ack(&self, ack: bool) {
+        self.with(|inner| inner.modem.ack = ack);
+    }
+
+    /// Margin and gateway count the network server answers link checks with, `None` to not answer
+    pub fn set_link_check_answer(&self, answer: Option<(u8, u8)>) {
+        self.with(|inner| inner.modem.link_check = answer);
+    }
+
+    /// Mark the simulated device as joined without going through `AT+JOIN`
+    pub fn set_joined(&self, joined: bool) {
+        self.with(|inner| inner.modem.joined = joined);
+    }
+
+    pub fn is_joined(&self) -> bool {
+        self.with(|inner| inner.modem.joined)
+    }
+
+    /// Maximum payload length reported by `AT+LW=LEN`
+    pub fn set_max_payload_len(&self, len: u8) {
+        self.with(|inner| inner.modem.max_payload_len = len);
+    }
+
+    /// Queue a downlink that is delivered in the RX window of the next uplink
+    pub fn queue_downlink(&self, downlink: SimulatedDownlink) {
+        self.with(|inner| inner.modem.queued_downlinks.push_back(downlink));
+    }
+
+    /// Emit a downlink straight away as `+MSG` URCs, like a Class C device receives it
+    pub fn push_downlink(&self, downlink: SimulatedDownlink) {
+        self.with(|inner| {
+            inner.modem.downlink_counter += 1;
+            emit_downlink(inner, "MSG", &downlink);
+            inner.emit("+MSG: Done");
+        });
+    }
+
+    /// Emit a packet received in TEST mode, if the module is receiving (`AT+TEST=RXLRPKT`)
+    pub fn push_test_packet(&self, payload: &[u8], rssi: i16, snr: i8) {
+        self.with(|inner| {
+            if !inner.modem.test_rx {
+                return;
+            }
+            let mut hex = String::new();
+            for byte in payload {
+                let _ = write!(hex, "{:02X}", byte);
+            }
+            inner.emit(&format!(
+                "+TEST: LEN:{}, RSSI:{}, SNR:{}",
+                payload.len(),
+                rssi,
+                snr
+            ));
+            inner.emit(&format!("+TEST: RX \"{}\"", hex));
+        });
+    }
+
+    /// Hang the module: it ignores everything until it is hard reset
+    pub fn wedge(&self) {
+        self.with(|inner| inner.modem.wedged = true);
+    }
+
+    /// Hang the module in the middle of its next uplink, after its `Start` URC and before `Done`
+    pub fn wedge_during_uplink(&self) {
+        self.with(|inner| inner.modem.wedge_on_uplink = true);
+    }
+
+    /// NRST line of the module, for [ResetPin](crate::client::ResetPin)
+    pub fn reset_pin(&self) -> SimulatedResetPin {
+        SimulatedResetPin {
+            simulator: self.clone(),
+        }
+    }
+
+    /// Lose the Class B beacon, as when it is missed for too long
+    pub fn lose_beacon(&self) {
+        self.with(|inner| {
+            if inner.modem.beacon_status == "LOCKED" {
+                inner.modem.beacon_status = "LOST";
+                inner.emit("+BEACON: LOST");
+            }
+        });
+    }
+
+    /// Whether the module is sleeping and ignoring commands
+    pub fn is_asleep(&self) -> bool {
+        self.with(|inner| inner.modem.asleep)
+    }
+
+    /// Emit a raw line (without `\r\n`) from the module
+    pub fn emit(&self, line: &str) {
+        self.with(|inner| inner.emit(line));
+    }
+
+    fn write_bytes(&self, buf: &[u8]) {
+        self.with(|inner| {
+            for byte in buf {
+                if inner.modem.wedged || inner.modem.held_in_reset {
+                    continue;
+                }
+                // 0xFF wake up bytes are not part of the command
+                if *byte == 0xFF {
+                    if inner.modem.asleep {
+                        inner.modem.asleep = false;
+                        inner.modem.wake_at = None;
+                        inner.emit("+LOWPOWER: WAKEUP");
+                    }
+                    continue;
+                }
+                // A sleeping module does not hear commands
+                if inner.modem.asleep {
+                    continue;
+                }
+                inner.line.push(*byte);
+                if inner.line.ends_with(b"\r\n") {
+                    let line = core::mem::take(&mut inner.line);
+                    let line = String::from_utf8_lossy(&line[..line.len() - 2]).into_owned();
+                    handle_command(inner, &line);
+                    if inner.modem.auto_low_power {
+                        inner.modem.asleep = true;
+                    }
+                }
+            }
+        });
+    }
+
+    fn read_bytes(inner: &mut Inner, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(inner.rx.len());
+        for (place, byte) in buf.iter_mut().zip(inner.rx.drain(..len)) {
+            *place = byte;
+        }
+        len
+    }
+}
+
+fn emit_downlink(inner: &mut Inner, cmd: &str, downlink: &SimulatedDownlink) {
+    let mut hex = String::new();
+    for byte in &downlink.payload {
+        let _ = write!(hex, "{:02X}", byte);
+    }
+    let multicast = if downlink.multicast { "MC, " } else { "" };
+    inner.emit(&format!(
+        "+{}: {}PORT: {}; RX: \"{}\"",
+        cmd, multicast, downlink.port, hex
+    ));
+    inner.emit(&format!(
+        "+{}: RXWIN1, RSSI {}, SNR {:.1}",
+        cmd, downlink.rssi, downlink.snr
+    ));
+}
+
+fn hex_bytes(s: &str) -> String {
+    s.chars().filter(|c| c.is_ascii_hexdigit()).collect()
+}
+
+fn parse_hex_u64(s: &str) -> u64 {
+    u64::from_str_radix(&hex_bytes(s), 16).unwrap_or_default()
+}
+
+fn parse_mhz(s: &str) -> u32 {
+    let mhz: f64 = s.parse().unwrap_or_default();
+    (mhz * 1_000_000.0).round() as u32
+}
+
+fn colon_hex(val: u64, bytes: usize) -> String {
+    let mut s = String::new();
+    for i in (0..bytes).rev() {
+        if !s.is_empty() {
+            s.push(':');
+        }
+        let _ = write!(s, "{:02X}", (val >> (i * 8)) & 0xFF);
+    }
+    s
+}
+
+fn handle_command(inner: &mut Inner, line: &str) {
+    inner.modem.commands.push(line.into());
+    let line = line.trim();
+    if line.is_empty() {
+        return;
+    }
+    if !line
+        .get(..2)
+        .is_some_and(|at| at.eq_ignore_ascii_case("AT"))
+    {
+        return;
+    }
+    let rest = &line[2..];
+    let (name, args) = match rest.split_once('=') {
+        Some((name, args)) => (name.to_uppercase(), Some(args)),
+        None => (rest.to_uppercase(), None),
+    };
+    let args: Vec<String> = args
+        .map(|args| {
+            args.split(',')
+                .map(|a| a.trim().trim_matches('"').to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let tag = name.trim_start_matches('+').to_string();
+
+    let upper = line.to_uppercase();
+    if let Some(index) = inner
+        .modem
+        .injected_errors
+        .iter()
+        .position(|(cmd, _)| upper.starts_with(cmd.as_str()))
+    {
+        let (_, message) = inner.modem.injected_errors.remove(index);
+        let tag = if tag.is_empty() { "AT" } else { tag.as_str() };
+        inner.emit(&format!("+{}: {}", tag, message));
+        return;
+    }
+
+    let arg = |i: usize| args.get(i).map(String::as_str).unwrap_or_default();
+    match name.as_str() {
+        "" => inner.emit("+AT: OK"),
+        "+VER" => inner.emit("+VER: 4.0.11"),
+        "+FDEFAULT" => {
+            // Only the configuration goes back to its defaults, not the simulated network
+            let modem = &mut inner.modem;
+            inner.modem = Modem {
+                link_check: modem.link_check,
+                injected_errors: core::mem::take(&mut modem.injected_errors),
+                queued_downlinks: core::mem::take(&mut modem.queued_downlinks),
+                commands: core::mem::take(&mut modem.commands),
+                ..Modem::default()
+            };
+            inner.emit("+FDEFAULT: OK");
+        }
+        "+RESET" => inner.reboot(),
+        "+LOWPOWER" => match arg(0).to_uppercase().as_str() {
+            "AUTOON" => {
+                inner.modem.auto_low_power = true;
+                inner.emit("+LOWPOWER: AUTOON");
+            }
+            "AUTOOFF" => {
+                inner.modem.auto_low_power = false;
+                inner.emit("+LOWPOWER: AUTOOFF");
+            }
+            "" => {
+                inner.modem.asleep = true;
+                inner.emit("+LOWPOWER: SLEEP");
+            }
+            ms => match ms.parse::<u64>() {
+                Ok(ms) => {
+                    inner.modem.asleep = true;
+                    inner.modem.wake_at = Some(Instant::now() + Duration::from_millis(ms));
+                    inner.emit("+LOWPOWER: SLEEP");
+                }
+                Err(_) => inner.emit("+LOWPOWER: ERROR(-1)"),
+            },
+        },
+        "+ID" => {
+            let key = arg(0).to_uppercase();
+            if args.len() > 1 {
+                match key.as_str() {
+                    "DEVEUI" => inner.modem.dev_eui = parse_hex_u64(arg(1)),
+                    "APPEUI" => inner.modem.app_eui = parse_hex_u64(arg(1)),
+                    "DEVADDR" => inner.modem.dev_addr = parse_hex_u64(arg(1)) as u32,
+                    _ => {}
+                }
+            }
+            let modem = &inner.modem;
+            let response = match key.as_str() {
+                "DEVEUI" => format!("+ID: DevEui, {}", colon_hex(modem.dev_eui, 8)),
+                "APPEUI" => format!("+ID: AppEui, {}", colon_hex(modem.app_eui, 8)),
+                _ => format!("+ID: DevAddr, {}", colon_hex(modem.dev_addr as u64, 4)),
+            };
+            inner.emit(&response);
+        }
+        "+KEY" => {
+            let response = format!("+KEY: {} {}", arg(0).to_uppercase(), hex_bytes(arg(1)));
+            inner.emit(&response);
+        }
+        "+MODE" => {
+            if !args.is_empty() {
+                inner.modem.mode = arg(0).to_uppercase();
+            }
+            let response = format!("+MODE: {}", inner.modem.mode);
+            inner.emit(&response);
+        }
+        "+DR" => {
+            let a = arg(0).to_uppercase();
+            if args.is_empty() {
+                let response = format!("+DR: DR{}", inner.modem.data_rate);
+                inner.emit(&response);
+            } else if let Some(dr) = a.strip_prefix("DR").and_then(|d| d.parse().ok()) {
+                inner.modem.data_rate = dr;
+            } else if let Ok(dr) = a.parse() {
+                inner.modem.data_rate = dr;
+            } else if a == "SCHEME" {
+                let response = format!("+DR: {}", inner.modem.region);
+                inner.emit(&response);
+                return;
+            } else {
+                inner.modem.region = a;
+                let response = format!("+DR: {}", inner.modem.region);
+                inner.emit(&response);
+                return;
+            }
+            let sf = 12u8.saturating_sub(inner.modem.data_rate).max(7);
+            let response = format!(
+                "+DR: {} DR{} SF{} BW125K",
+                inner.modem.region, inner.modem.data_rate, sf
+            );
+            inner.emit(&response);
+        }
+        "+CH" => {
+            let channels = &mut inner.modem.channels;
+            let response = match args.len() {
+                0 => {
+                    let enabled: Vec<_> = channels.iter().filter(|c| c.4).collect();
+                    let mut response = format!("+CH: {}", enabled.len());
+                    for (index, frequency, dr_min, dr_max, _) in enabled {
+                        let _ = write!(
+                            response,
+                            "; {},{},DR{},DR{}",
+                            index, frequency, dr_min, dr_max
+                        );
+                    }
+                    response
+                }
+                _ if arg(0).eq_ignore_ascii_case("NUM") => {
+                    let (first, last) = arg(1).split_once('-').unwrap_or_default();
+                    let (first, last) = (first.parse().unwrap_or(0), last.parse().unwrap_or(0));
+                    for channel in channels.iter_mut() {
+                        channel.4 = (first..=last).contains(&channel.0);
+                    }
+                    format!("+CH: NUM, {}-{}", first, last)
+                }
+                2 => {
+                    let index: u8 = arg(0).parse().unwrap_or_default();
+                    let on = arg(1).eq_ignore_ascii_case("ON");
+                    for channel in channels.iter_mut().filter(|c| c.0 == index) {
+                        channel.4 = on;
+                    }
+                    format!("+CH: CH{} {}", index, if on { "ON" } else { "OFF" })
+                }
+                _ => {
+                    let index: u8 = arg(0).parse().unwrap_or_default();
+                    let frequency = parse_mhz(arg(1));
+                    let dr_min = arg(2).parse().unwrap_or_default();
+                    let dr_max = arg(3).parse().unwrap_or_default();
+                    channels.retain(|c| c.0 != index);
+                    if frequency != 0 {
+                        channels.push((index, frequency, dr_min, dr_max, true));
+                        channels.sort_by_key(|c| c.0);
+                    }
+                    format!("+CH: {},{},DR{},DR{}", index, frequency, dr_min, dr_max)
+                }
+            };
+            inner.emit(&response);
+        }
+        "+RXWIN1" => {
+            let response = if args.is_empty() {
+                let channels = &inner.modem.rx1_channels;
+                let mut response = format!("+RXWIN1: ON; {}", channels.len());
+                for (index, frequency) in channels {
+                    let _ = write!(response, "; {},{}", index, frequency);
+                }
+                response
+            } else {
+                let index: u8 = arg(0).parse().unwrap_or_default();
+                let frequency = parse_mhz(arg(1));
+                let channels = &mut inner.modem.rx1_channels;
+                channels.retain(|c| c.0 != index);
+                channels.push((index, frequency));
+                channels.sort_by_key(|c| c.0);
+                format!("+RXWIN1: {},{}", index, frequency)
+            };
+            inner.emit(&response);
+        }
+        "+RXWIN2" => {
+            if !args.is_empty() {
+                let dr = arg(1).trim_start_matches("DR").parse().unwrap_or_default();
+                inner.modem.rx2 = (parse_mhz(arg(0)), dr);
+            }
+            let (frequency, dr) = inner.modem.rx2;
+            let response = format!(
+                "+RXWIN2: {}.{:06},DR{}",
+                frequency / 1_000_000,
+                frequency % 1_000_000,
+                dr
+            );
+            inner.emit(&response);
+        }
+        "+DELAY" => {
+            let response = if args.is_empty() {
+                let delays: Vec<String> = inner
+                    .modem
+                    .delays
+                    .iter()
+                    .map(|(delay, ms)| format!("{}, {}", delay, ms))
+                    .collect();
+                format!("+DELAY: {}", delays.join("; "))
+            } else {
+                let name = arg(0).to_uppercase();
+                let ms = arg(1).parse().unwrap_or_default();
+                match inner.modem.delays.iter_mut().find(|d| d.0 == name) {
+                    Some(delay) => {
+                        delay.1 = ms;
+                        format!("+DELAY: {}, {}", delay.0, ms)
+                    }
+                    None => "+DELAY: ERROR(-1)".into(),
+                }
+            };
+            inner.emit(&response);
+        }
+        "+CLASS" => {
+            match arg(0) {
+                "" | "?" => {}
+                class => inner.modem.class = class.chars().next().unwrap_or('A'),
+            }
+            let response = format!("+CLASS: {}", inner.modem.class);
+            inner.emit(&response);
+            if inner.modem.class != 'B' {
+                inner.modem.beacon_status = "IDLE";
+            } else if !args.is_empty() {
+                inner.emit("+BEACON: SEARCHING");
+                inner.modem.beacon_status = if inner.modem.joined {
+                    "LOCKED"
+                } else {
+                    "FAILED"
+                };
+                let response = format!("+BEACON: {}", inner.modem.beacon_status);
+                inner.emit(&response);
+                if inner.modem.joined {
+                    let response =
+                        format!("+BEACON: PINGSLOT, {}", inner.modem.ping_slot_periodicity);
+                    inner.emit(&response);
+                }
+            }
+        }
+        "+BEACON" => {
+            let response = match arg(0).to_uppercase().as_str() {
+                "" => {
+                    let (frequency, dr) = inner.modem.beacon;
+                    format!("+BEACON: {},DR{}", frequency, dr)
+                }
+                "STATUS" => format!("+BEACON: STATUS, {}", inner.modem.beacon_status),
+                "PERIOD" => {
+                    match arg(1).parse::<u8>() {
+                        Ok(periodicity) if periodicity <= 7 => {
+                            inner.modem.ping_slot_periodicity = periodicity
+                        }
+                        Ok(_) => return inner.emit("+BEACON: ERROR(-1)"),
+                        Err(_) => {}
+                    }
+                    format!("+BEACON: PERIOD, {}", inner.modem.ping_slot_periodicity)
+                }
+                frequency => {
+                    let dr = arg(1).to_uppercase();
+                    inner.modem.beacon = (
+                        parse_mhz(frequency),
+                        dr.trim_start_matches("DR").parse().unwrap_or_default(),
+                    );
+                    let (frequency, dr) = inner.modem.beacon;
+                    format!("+BEACON: {},DR{}", frequency, dr)
+                }
+            };
+            inner.emit(&response);
+        }
+        "+ADR" => {
+            match arg(0).to_uppercase().as_str() {
+                "ON" => inner.modem.adr = true,
+                "OFF" => inner.modem.adr = false,
+                _ => {}
+            }
+            let response = format!("+ADR: {}", if inner.modem.adr { "ON" } else { "OFF" });
+            inner.emit(&response);
+        }
+        "+PORT" => {
+            if let Ok(port) = arg(0).parse() {
+                inner.modem.port = port;
+            }
+            let response = format!("+PORT: {}", inner.modem.port);
+            inner.emit(&response);
+        }
+        "+RETRY" => {
+            if let Ok(retry) = arg(0).parse() {
+                inner.modem.retry = retry;
+            }
+            let response = format!("+RETRY: {}", inner.modem.retry);
+            inner.emit(&response);
+        }
+        "+REPT" => {
+            if let Ok(repeat) = arg(0).parse() {
+                inner.modem.repeat = repeat;
+            }
+            let response = format!("+REPT: {}", inner.modem.repeat);
+            inner.emit(&response);
+        }
+        "+LW" => match arg(0).to_uppercase().as_str() {
+            "ULDL" => {
+                if let (Ok(uplink), Ok(downlink)) = (arg(1).parse(), arg(2).parse()) {
+                    inner.modem.uplink_counter = uplink;
+                    inner.modem.downlink_counter = downlink;
+                }
+                let response = format!(
+                    "+LW: ULDL, {}, {}",
+                    inner.modem.uplink_counter, inner.modem.downlink_counter
+                );
+                inner.emit(&response);
+            }
+            "LEN" => {
+                let response = format!("+LW: LEN, {}", inner.modem.max_payload_len);
+                inner.emit(&response);
+            }
+            name @ ("DC" | "JDC" | "NET") => {
+                let switch = match name {
+                    "DC" => &mut inner.modem.duty_cycle,
+                    "JDC" => &mut inner.modem.join_duty_cycle,
+                    _ => &mut inner.modem.public_network,
+                };
+                match arg(1).to_uppercase().as_str() {
+                    "ON" => *switch = true,
+                    "OFF" => *switch = false,
+                    _ => {}
+                }
+                let on = if *switch { "ON" } else { "OFF" };
+                let response = format!("+LW: {}, {}", name, on);
+                inner.emit(&response);
+            }
+            "VER" => {
+                match arg(1).to_uppercase().as_str() {
+                    "" => {}
+                    v @ ("V100" | "V101" | "V102" | "V102B" | "V103" | "V102A" | "V110") => {
+                        inner.modem.lorawan_version = v.into()
+                    }
+                    _ => return inner.emit("+LW: ERROR(-1)"),
+                }
+                let response = format!("+LW: VER, {}", inner.modem.lorawan_version);
+                inner.emit(&response);
+            }
+            "MC" => {
+                match arg(1).to_uppercase().as_str() {
+                    "ON" if args.len() == 5 => {
+                        inner.modem.multicast = Some(parse_hex_u64(arg(2)) as u32)
+                    }
+                    "ON" => return inner.emit("+LW: ERROR(-11)"),
+                    "OFF" => inner.modem.multicast = None,
+                    _ => {}
+                }
+                let response = match inner.modem.multicast {
+                    Some(dev_addr) => format!("+LW: MC, ON, {:08X}", dev_addr),
+                    None => "+LW: MC, OFF".into(),
+                };
+                inner.emit(&response);
+            }
+            "CDR" => {
+                let response = format!("+LW: CDR, DR{}", inner.modem.data_rate);
+                inner.emit(&response);
+            }
+            "BAT" => {
+                if let Ok(level) = arg(1).parse() {
+                    inner.modem.battery_level = level;
+                }
+                let response = format!("+LW: BAT, {}", inner.modem.battery_level);
+                inner.emit(&response);
+            }
+            _ => inner.emit("+LW: ERROR(-1)"),
+        },
+        "+TEST" => {
+            if inner.modem.mode != "TEST" {
+                inner.emit("+TEST: ERROR(-12)");
+                return;
+            }
+            match arg(0).to_uppercase().as_str() {
+                "RFCFG" => {
+                    if args.len() == 10 {
+                        let on = |i: usize| arg(i).eq_ignore_ascii_case("ON");
+                        inner.modem.test_rf = RfConfig {
+                            frequency_hz: parse_mhz(arg(1)),
+                            spreading_factor: arg(2)[2..].parse().unwrap_or_default(),
+                            bandwidth_khz: arg(3).parse().unwrap_or_default(),
+                            tx_preamble: arg(4).parse().unwrap_or_default(),
+                            rx_preamble: arg(5).parse().unwrap_or_default(),
+                            tx_power_dbm: arg(6).parse().unwrap_or_default(),
+                            crc: on(7),
+                            iq_inverted: on(8),
+                            public: on(9),
+                        };
+                    } else if args.len() > 1 {
+                        inner.emit("+TEST: ERROR(-11)");
+                        return;
+                    }
+                    let rf = &inner.modem.test_rf;
+                    let on_off = |on: bool| if on { "ON" } else { "OFF" };
+                    let response = format!(
+                        "+TEST: RFCFG F:{}, SF{}, BW{}K, TXPR:{}, RXPR:{}, POW:{}dBm, CRC:{}, IQ:{}, NET:{}",
+                        rf.frequency_hz,
+                        rf.spreading_factor,
+                        rf.bandwidth_khz,
+                        rf.tx_preamble,
+                        rf.rx_preamble,
+                        rf.tx_power_dbm,
+                        on_off(rf.crc),
+                        on_off(rf.iq_inverted),
+                        on_off(rf.public)
+                    );
+                    inner.emit(&response);
+                }
+                cmd @ ("TXLRPKT" | "TXLRSTR") => {
+                    let response = format!("+TEST: {} \"{}\"", cmd, arg(1));
+                    inner.emit(&response);
+                    inner.emit("+TEST: TX DONE");
+                }
+                cmd @ ("TXCW" | "TXCLORA") => {
+                    let response = format!("+TEST: {}", cmd);
+                    inner.emit(&response);
+                }
+                "RXLRPKT" => {
+                    inner.modem.test_rx = true;
+                    inner.emit("+TEST: RXLRPKT");
+                }
+                "STOP" => {
+                    inner.modem.test_rx = false;
+                    inner.emit("+TEST: STOP");
+                }
+                _ => inner.emit("+TEST: ERROR(-1)"),
+            }
+        }
+        "+JOIN" => {
+            if !args.is_empty() {
+                let response = format!("+JOIN: Auto-Join {}", args.join(", "));
+                inner.emit(&response);
+                return;
+            }
+            if inner.modem.mode != "LWOTAA" {
+                inner.emit("+JOIN: ERROR(-12)");
+                return;
+            }
+            if inner.modem.joined {
+                inner.emit("+JOIN: Joined already");
+                return;
+            }
+            inner.emit("+JOIN: Start");
+            inner.emit("+JOIN: NORMAL");
+            if inner.modem.join_unanswered {
+                // Neither a join accept nor a failure, only `Done` follows
+            } else if inner.modem.join_accept {
+                inner.modem.joined = true;
+                inner.modem.uplink_counter = 0;
+                inner.modem.downlink_counter = 0;
+                inner.emit("+JOIN: Network joined");
+                let response = format!(
+                    "+JOIN: NetID 000013 DevAddr {}",
+                    colon_hex(inner.modem.dev_addr as u64, 4)
+                );
+                inner.emit(&response);
+            } else {
+                inner.emit("+JOIN: Join failed");
+            }
+            inner.emit("+JOIN: Done");
+        }
+        "+MSG" if args.is_empty() => {
+            if !inner.modem.joined && inner.modem.mode != "LWABP" {
+                inner.emit("+MSG: Please join network first");
+                return;
+            }
+            inner.modem.uplink_counter += 1;
+            inner.emit("+MSG: Start");
+            if core::mem::take(&mut inner.modem.wedge_on_uplink) {
+                inner.modem.wedged = true;
+                return;
+            }
+            if let Some((margin, gateways)) = inner.modem.link_check {
+                inner.modem.downlink_counter += 1;
+                inner.emit(&format!("+MSG: Link {}, {}", margin, gateways));
+                inner.emit("+MSG: RXWIN1, RSSI -98, SNR 7.5");
+            }
+            inner.emit("+MSG: Done");
+        }
+        "+MSGHEX" | "+CMSGHEX" => {
+            let confirmed = name == "+CMSGHEX";
+            // ABP devices are activated as soon as their session is provisioned
+            if !inner.modem.joined && inner.modem.mode != "LWABP" {
+                let response = format!("+{}: Please join network first", tag);
+                inner.emit(&response);
+                return;
+            }
+            if hex_bytes(arg(0)).len() / 2 > inner.modem.max_payload_len as usize {
+                let response = format!("+{}: Length error {}", tag, inner.modem.max_payload_len);
+                inner.emit(&response);
+                return;
+            }
+            inner.modem.uplink_counter += 1;
+            let response = format!("+{}: Start", tag);
+            inner.emit(&response);
+            if core::mem::take(&mut inner.modem.wedge_on_uplink) {
+                inner.modem.wedged = true;
+                return;
+            }
+            let downlink = inner.modem.queued_downlinks.pop_front();
+            if confirmed {
+                let response = format!("+{}: Wait ACK", tag);
+                inner.emit(&response);
+                if inner.modem.ack {
+                    let response = format!("+{}: ACK Received", tag);
+                    inner.emit(&response);
+                }
+            }
+            if let Some(downlink) = downlink {
+                inner.modem.downlink_counter += 1;
+                emit_downlink(inner, &tag, &downlink);
+            } else if confirmed && inner.modem.ack {
+                let response = format!("+{}: RXWIN1, RSSI -106, SNR 4.0", tag);
+                inner.emit(&response);
+            }
+            let response = format!("+{}: Done", tag);
+            inner.emit(&response);
+        }
+        _ => {
+            let tag = if tag.is_empty() { "AT".into() } else { tag };
+            let response = format!("+{}: ERROR(-10)", tag);
+            inner.emit(&response);
+        }
+    }
+}
+
+/// NRST line of a [LoraE5Simulator]: low holds the module in reset, high boots it
+#[derive(Clone)]
+pub struct SimulatedResetPin {
+    simulator: LoraE5Simulator,
+}
+
+impl embedded_hal::digital::ErrorType for SimulatedResetPin {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for SimulatedResetPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.simulator
+            .with(|inner| inner.modem.held_in_reset = true);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.simulator.with(|inner| {
+            if inner.modem.held_in_reset {
+                inner.modem.held_in_reset = false;
+                inner.reboot();
+            }
+        });
+        Ok(())
+    }
+}
+
+impl embedded_io::ErrorType for LoraE5Simulator {
+    type Error = Infallible;
+}
+
+impl embedded_io_async::Write for LoraE5Simulator {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+}
+
+impl embedded_io_async::Read for LoraE5Simulator {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| {
+            self.with(|inner| {
+                if inner.rx.is_empty() {
+                    inner.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                } else {
+                    Poll::Ready(Ok(Self::read_bytes(inner, buf)))
+                }
+            })
+        })
+        .await
+    }
+}
+
+impl embedded_io::Write for LoraE5Simulator {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl embedded_io::Read for LoraE5Simulator {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let (lock, condvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+        inner.wake_if_due();
+        while inner.rx.is_empty() {
+            inner = match inner.modem.wake_at {
+                Some(at) => {
+                    let timeout = at.saturating_duration_since(Instant::now());
+                    condvar.wait_timeout(inner, timeout).unwrap().0
+                }
+                None => condvar.wait(inner).unwrap(),
+            };
+            inner.wake_if_due();
+        }
+        Ok(Self::read_bytes(&mut inner, buf))
+    }
+}
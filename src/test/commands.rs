@@ -0,0 +1,232 @@
+use super::responses::RfConfigResponse;
+use crate::test::types::{RfConfig, MAX_TEST_PACKET_LEN};
+use crate::NoResponse;
+use atat::{AtatCmd, Error, InternalError};
+use core::str::FromStr;
+
+/// The rest of a `+TEST: <name> ...` response
+fn test_reply<'a>(resp: Result<&'a [u8], InternalError>, name: &str) -> Result<&'a str, Error> {
+    let resp = resp.map_err(Error::from)?;
+    let resp = core::str::from_utf8(resp).map_err(|_| Error::Parse)?;
+    resp.trim()
+        .strip_prefix(name)
+        .map(str::trim)
+        .ok_or(Error::Parse)
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+/// 4.31 TEST RFCFG get
+/// Get the radio settings of TEST mode
+#[derive(Clone, Debug)]
+pub struct TestRfConfigGet {}
+
+impl AtatCmd for TestRfConfigGet {
+    type Response = RfConfigResponse;
+
+    const MAX_LEN: usize = 15;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..15].copy_from_slice(b"AT+TEST=RFCFG\r\n");
+        15
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let config = RfConfig::from_str(test_reply(resp, "RFCFG")?).map_err(|_| Error::Parse)?;
+        Ok(RfConfigResponse { config })
+    }
+}
+
+/// 4.31 TEST RFCFG set
+/// Set the frequency, spreading factor, bandwidth, preambles, TX power, CRC, IQ inversion and
+/// sync word used in TEST mode
+#[derive(Clone, Debug)]
+pub struct TestRfConfigSet {
+    pub config: RfConfig,
+}
+
+impl AtatCmd for TestRfConfigSet {
+    type Response = RfConfigResponse;
+
+    const MAX_LEN: usize = 64;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let config = &self.config;
+        let _ = write!(
+            buf,
+            "AT+TEST=RFCFG,{}.{:06},SF{},{},{},{},{},{},{},{}\r\n",
+            config.frequency_hz / 1_000_000,
+            config.frequency_hz % 1_000_000,
+            config.spreading_factor,
+            config.bandwidth_khz,
+            config.tx_preamble,
+            config.rx_preamble,
+            config.tx_power_dbm,
+            on_off(config.crc),
+            on_off(config.iq_inverted),
+            on_off(config.public),
+        );
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        let config = RfConfig::from_str(test_reply(resp, "RFCFG")?).map_err(|_| Error::Parse)?;
+        Ok(RfConfigResponse { config })
+    }
+}
+
+/// 4.31 TEST TXLRPKT
+/// Send a LoRa packet with the TEST mode radio settings.
+/// `+TEST: TX DONE` follows once it has been sent.
+#[derive(Clone, Debug)]
+pub struct TestTxPacket<'a> {
+    pub data: &'a [u8],
+}
+
+impl AtatCmd for TestTxPacket<'_> {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 20 + MAX_TEST_PACKET_LEN * 2;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+TEST=TXLRPKT,\"");
+        for byte in self.data.iter().take(MAX_TEST_PACKET_LEN) {
+            let _ = write!(buf, "{:02X}", byte);
+        }
+        let _ = write!(buf, "\"\r\n");
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "TXLRPKT")?;
+        Ok(NoResponse)
+    }
+}
+
+/// 4.31 TEST TXLRSTR
+/// Send a text as LoRa packet with the TEST mode radio settings.
+/// `+TEST: TX DONE` follows once it has been sent.
+#[derive(Clone, Debug)]
+pub struct TestTxString<'a> {
+    pub text: &'a str,
+}
+
+impl TestTxString<'_> {
+    /// The text is sent between quotes, so it can't hold `"` or line breaks
+    pub fn is_valid(&self) -> bool {
+        !self.text.contains(['"', '\r', '\n'])
+    }
+}
+
+impl AtatCmd for TestTxString<'_> {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 20 + MAX_TEST_PACKET_LEN;
+
+    fn write(&self, mut buf: &mut [u8]) -> usize {
+        use embedded_io::Write;
+        let len = buf.len();
+        let _ = write!(buf, "AT+TEST=TXLRSTR,\"{}\"\r\n", self.text);
+        len - buf.len()
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "TXLRSTR")?;
+        Ok(NoResponse)
+    }
+}
+
+/// 4.31 TEST RXLRPKT
+/// Continuously receive LoRa packets with the TEST mode radio settings, until stopped.
+/// Every packet is reported as `+TEST: LEN:..., RSSI:..., SNR:...` followed by `+TEST: RX "..."`.
+#[derive(Clone, Debug)]
+pub struct TestRxStart {}
+
+impl AtatCmd for TestRxStart {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 17;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..17].copy_from_slice(b"AT+TEST=RXLRPKT\r\n");
+        17
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "RXLRPKT")?;
+        Ok(NoResponse)
+    }
+}
+
+/// 4.31 TEST TXCW
+/// Transmit a continuous wave at the TEST mode frequency and TX power, until stopped
+#[derive(Clone, Debug)]
+pub struct TestTxContinuousWave {}
+
+impl AtatCmd for TestTxContinuousWave {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 14;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..14].copy_from_slice(b"AT+TEST=TXCW\r\n");
+        14
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "TXCW")?;
+        Ok(NoResponse)
+    }
+}
+
+/// 4.31 TEST TXCLORA
+/// Transmit continuous LoRa modulation with the TEST mode radio settings, until stopped
+#[derive(Clone, Debug)]
+pub struct TestTxContinuousLora {}
+
+impl AtatCmd for TestTxContinuousLora {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 17;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..17].copy_from_slice(b"AT+TEST=TXCLORA\r\n");
+        17
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "TXCLORA")?;
+        Ok(NoResponse)
+    }
+}
+
+/// 4.31 TEST STOP
+/// Stop receiving or transmitting
+#[derive(Clone, Debug)]
+pub struct TestStop {}
+
+impl AtatCmd for TestStop {
+    type Response = NoResponse;
+
+    const MAX_LEN: usize = 14;
+
+    fn write(&self, buf: &mut [u8]) -> usize {
+        buf[..14].copy_from_slice(b"AT+TEST=STOP\r\n");
+        14
+    }
+
+    fn parse(&self, resp: Result<&[u8], InternalError>) -> Result<Self::Response, Error> {
+        test_reply(resp, "STOP")?;
+        Ok(NoResponse)
+    }
+}
@@ -0,0 +1,339 @@
+//! # TEST mode
+//!
+//! Point-to-point LoRa between LoRa-E5 modules, without a LoRaWAN network. Put the module in
+//! TEST mode with [join_mode_set](crate::client::asynch::SeeedLoraE5Client::join_mode_set) first.
+//!
+//! The `rf_test_*` functions key the radio for RF (pre-)compliance testing. They switch to TEST
+//! mode themselves and switch back to the previous mode on
+//! [rf_test_stop](crate::client::asynch::SeeedLoraE5Client::rf_test_stop).
+
+pub mod commands;
+pub mod responses;
+pub mod types;
+pub mod urc;
+
+use embassy_time::Duration;
+
+/// Airtime of the longest packet at SF12, 125 kHz, with some margin, for `+TEST: TX DONE`
+pub(crate) const TEST_TX_TIMEOUT: Duration = Duration::from_millis(12000);
+
+#[cfg(feature = "async")]
+pub mod asynch {
+    use crate::client::asynch::SeeedLoraE5Client;
+    use crate::error::LoraE5Error;
+    use crate::lora::types::LoraJoinMode;
+    use crate::lora::verify_echo;
+    use crate::test::commands;
+    use crate::test::types::{RfConfig, TestPacket, MAX_TEST_PACKET_LEN};
+    use crate::test::TEST_TX_TIMEOUT;
+    use embassy_time::with_timeout;
+    use embedded_io_async::Write;
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub async fn test_rf_config(&mut self) -> Result<RfConfig, LoraE5Error> {
+            let command = commands::TestRfConfigGet {};
+            let response = self.client.send(&command).await?;
+            Ok(response.config)
+        }
+
+        pub async fn test_rf_config_set(
+            &mut self,
+            config: RfConfig,
+        ) -> Result<RfConfig, LoraE5Error> {
+            let command = commands::TestRfConfigSet {
+                config: config.clone(),
+            };
+            let response = self.client.send(&command).await?;
+            verify_echo(&response.config, &config)?;
+            Ok(response.config)
+        }
+
+        /// Wait for `+TEST: TX DONE`, failing with [Timeout](LoraE5Error::Timeout) if it never comes
+        async fn test_tx_done_wait(&mut self) -> Result<(), LoraE5Error> {
+            with_timeout(TEST_TX_TIMEOUT, self.state.test_tx_done.wait())
+                .await
+                .map_err(|_| LoraE5Error::Timeout)
+        }
+
+        /// Send a packet and wait until it has been sent
+        pub async fn test_send(&mut self, data: &[u8]) -> Result<(), LoraE5Error> {
+            if data.len() > MAX_TEST_PACKET_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            self.state.test_tx_done.reset();
+            let command = commands::TestTxPacket { data };
+            self.client.send(&command).await?;
+            self.test_tx_done_wait().await
+        }
+
+        /// Send a text packet and wait until it has been sent
+        pub async fn test_send_str(&mut self, text: &str) -> Result<(), LoraE5Error> {
+            if text.len() > MAX_TEST_PACKET_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            let command = commands::TestTxString { text };
+            if !command.is_valid() {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            self.state.test_tx_done.reset();
+            self.client.send(&command).await?;
+            self.test_tx_done_wait().await
+        }
+
+        /// Start receiving packets, see [test_receive](Self::test_receive)
+        pub async fn test_receive_start(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::TestRxStart {};
+            self.client.send(&command).await?;
+            Ok(())
+        }
+
+        /// Wait for the next received packet
+        pub async fn test_receive(&mut self) -> Result<TestPacket, LoraE5Error> {
+            Ok(self.state.test_packets.receive().await)
+        }
+
+        /// The next received packet, if any
+        pub fn test_try_receive(&mut self) -> Option<TestPacket> {
+            self.state.test_packets.try_receive().ok()
+        }
+
+        /// Stop receiving or transmitting
+        pub async fn test_stop(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::TestStop {};
+            self.client.send(&command).await?;
+            Ok(())
+        }
+
+        /// Switch to TEST mode, remembering the mode to restore
+        async fn rf_test_enter(&mut self) -> Result<(), LoraE5Error> {
+            let mode = self.join_mode().await?;
+            if mode != LoraJoinMode::Test
+                && self.join_mode_set(LoraJoinMode::Test).await? != LoraJoinMode::Test
+            {
+                return Err(LoraE5Error::UnavailableInCurrentMode);
+            }
+            if self.rf_test_restore_mode.is_none() {
+                self.rf_test_restore_mode = Some(mode);
+            }
+            Ok(())
+        }
+
+        /// TEST mode radio settings. Fails unless the module is in TEST mode.
+        pub async fn rf_test_config(&mut self) -> Result<RfConfig, LoraE5Error> {
+            if self.join_mode().await? != LoraJoinMode::Test {
+                return Err(LoraE5Error::UnavailableInCurrentMode);
+            }
+            self.test_rf_config().await
+        }
+
+        async fn rf_test_tune(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            self.rf_test_enter().await?;
+            let config = RfConfig {
+                frequency_hz,
+                tx_power_dbm,
+                ..self.test_rf_config().await?
+            };
+            self.test_rf_config_set(config).await
+        }
+
+        /// Transmit a continuous wave until [rf_test_stop](Self::rf_test_stop)
+        pub async fn rf_test_cw(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            let config = self.rf_test_tune(frequency_hz, tx_power_dbm).await?;
+            self.client.send(&commands::TestTxContinuousWave {}).await?;
+            Ok(config)
+        }
+
+        /// Transmit continuous LoRa modulation until [rf_test_stop](Self::rf_test_stop).
+        /// Spreading factor and bandwidth are taken from the current TEST mode radio settings.
+        pub async fn rf_test_clora(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            let config = self.rf_test_tune(frequency_hz, tx_power_dbm).await?;
+            self.client.send(&commands::TestTxContinuousLora {}).await?;
+            Ok(config)
+        }
+
+        /// Stop transmitting and switch back to the mode from before the RF test
+        pub async fn rf_test_stop(&mut self) -> Result<(), LoraE5Error> {
+            let stopped = self.test_stop().await;
+            // The mode is only forgotten once restored, a failed stop can be retried
+            if let Some(mode) = self.rf_test_restore_mode.clone() {
+                let restored = self.join_mode_set(mode.clone()).await?;
+                verify_echo(restored, mode)?;
+                self.rf_test_restore_mode = None;
+            }
+            stopped
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    use crate::client::blocking::{poll_until, SeeedLoraE5Client, RECEIVE_TIMEOUT};
+    use crate::error::LoraE5Error;
+    use crate::lora::types::LoraJoinMode;
+    use crate::lora::verify_echo;
+    use crate::test::commands;
+    use crate::test::types::{RfConfig, TestPacket, MAX_TEST_PACKET_LEN};
+    use crate::test::TEST_TX_TIMEOUT;
+    use embedded_io::Write;
+
+    impl<'a, W: Write, const INGRESS_BUF_SIZE: usize, const QUEUE_SIZE: usize, R>
+        SeeedLoraE5Client<'a, W, INGRESS_BUF_SIZE, QUEUE_SIZE, R>
+    {
+        pub fn test_rf_config(&mut self) -> Result<RfConfig, LoraE5Error> {
+            let command = commands::TestRfConfigGet {};
+            let response = self.client.send(&command)?;
+            Ok(response.config)
+        }
+
+        pub fn test_rf_config_set(&mut self, config: RfConfig) -> Result<RfConfig, LoraE5Error> {
+            let command = commands::TestRfConfigSet {
+                config: config.clone(),
+            };
+            let response = self.client.send(&command)?;
+            verify_echo(&response.config, &config)?;
+            Ok(response.config)
+        }
+
+        /// Wait for `+TEST: TX DONE`, failing with [Timeout](LoraE5Error::Timeout) if it never comes
+        fn test_tx_done_wait(&mut self) -> Result<(), LoraE5Error> {
+            poll_until(TEST_TX_TIMEOUT, || self.state.test_tx_done.try_take())
+        }
+
+        /// Send a packet and wait until it has been sent
+        pub fn test_send(&mut self, data: &[u8]) -> Result<(), LoraE5Error> {
+            if data.len() > MAX_TEST_PACKET_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            self.state.test_tx_done.reset();
+            let command = commands::TestTxPacket { data };
+            self.client.send(&command)?;
+            self.test_tx_done_wait()
+        }
+
+        /// Send a text packet and wait until it has been sent
+        pub fn test_send_str(&mut self, text: &str) -> Result<(), LoraE5Error> {
+            if text.len() > MAX_TEST_PACKET_LEN {
+                return Err(LoraE5Error::LengthError);
+            }
+            let command = commands::TestTxString { text };
+            if !command.is_valid() {
+                return Err(LoraE5Error::InvalidParameter);
+            }
+            self.state.test_tx_done.reset();
+            self.client.send(&command)?;
+            self.test_tx_done_wait()
+        }
+
+        /// Start receiving packets, see [test_receive](Self::test_receive)
+        pub fn test_receive_start(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::TestRxStart {};
+            self.client.send(&command)?;
+            Ok(())
+        }
+
+        /// Wait for the next received packet.
+        /// Fails with [Timeout](LoraE5Error::Timeout) if none came within [RECEIVE_TIMEOUT].
+        pub fn test_receive(&mut self) -> Result<TestPacket, LoraE5Error> {
+            poll_until(RECEIVE_TIMEOUT, || {
+                self.state.test_packets.try_receive().ok()
+            })
+        }
+
+        /// The next received packet, if any
+        pub fn test_try_receive(&mut self) -> Option<TestPacket> {
+            self.state.test_packets.try_receive().ok()
+        }
+
+        /// Stop receiving or transmitting
+        pub fn test_stop(&mut self) -> Result<(), LoraE5Error> {
+            let command = commands::TestStop {};
+            self.client.send(&command)?;
+            Ok(())
+        }
+
+        /// Switch to TEST mode, remembering the mode to restore
+        fn rf_test_enter(&mut self) -> Result<(), LoraE5Error> {
+            let mode = self.join_mode()?;
+            if mode != LoraJoinMode::Test
+                && self.join_mode_set(LoraJoinMode::Test)? != LoraJoinMode::Test
+            {
+                return Err(LoraE5Error::UnavailableInCurrentMode);
+            }
+            if self.rf_test_restore_mode.is_none() {
+                self.rf_test_restore_mode = Some(mode);
+            }
+            Ok(())
+        }
+
+        /// TEST mode radio settings. Fails unless the module is in TEST mode.
+        pub fn rf_test_config(&mut self) -> Result<RfConfig, LoraE5Error> {
+            if self.join_mode()? != LoraJoinMode::Test {
+                return Err(LoraE5Error::UnavailableInCurrentMode);
+            }
+            self.test_rf_config()
+        }
+
+        fn rf_test_tune(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            self.rf_test_enter()?;
+            let config = RfConfig {
+                frequency_hz,
+                tx_power_dbm,
+                ..self.test_rf_config()?
+            };
+            self.test_rf_config_set(config)
+        }
+
+        /// Transmit a continuous wave until [rf_test_stop](Self::rf_test_stop)
+        pub fn rf_test_cw(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            let config = self.rf_test_tune(frequency_hz, tx_power_dbm)?;
+            self.client.send(&commands::TestTxContinuousWave {})?;
+            Ok(config)
+        }
+
+        /// Transmit continuous LoRa modulation until [rf_test_stop](Self::rf_test_stop).
+        /// Spreading factor and bandwidth are taken from the current TEST mode radio settings.
+        pub fn rf_test_clora(
+            &mut self,
+            frequency_hz: u32,
+            tx_power_dbm: i8,
+        ) -> Result<RfConfig, LoraE5Error> {
+            let config = self.rf_test_tune(frequency_hz, tx_power_dbm)?;
+            self.client.send(&commands::TestTxContinuousLora {})?;
+            Ok(config)
+        }
+
+        /// Stop transmitting and switch back to the mode from before the RF test
+        pub fn rf_test_stop(&mut self) -> Result<(), LoraE5Error> {
+            let stopped = self.test_stop();
+            // The mode is only forgotten once restored, a failed stop can be retried
+            if let Some(mode) = self.rf_test_restore_mode.clone() {
+                let restored = self.join_mode_set(mode.clone())?;
+                verify_echo(restored, mode)?;
+                self.rf_test_restore_mode = None;
+            }
+            stopped
+        }
+    }
+}
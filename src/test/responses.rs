@@ -0,0 +1,10 @@
+use crate::test::types::RfConfig;
+
+/// TEST RFCFG response
+/// Example return `RFCFG F:868000000, SF7, BW125K, TXPR:8, RXPR:8, POW:14dBm, CRC:ON, IQ:OFF, NET:OFF`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RfConfigResponse {
+    pub config: RfConfig,
+}
+
+impl atat::AtatResp for RfConfigResponse {}
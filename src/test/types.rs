@@ -0,0 +1,91 @@
+use core::str::FromStr;
+
+/// Radio settings of TEST mode, as set by `AT+TEST=RFCFG` and reported as
+/// `RFCFG F:868000000, SF7, BW125K, TXPR:8, RXPR:8, POW:14dBm, CRC:ON, IQ:OFF, NET:OFF`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RfConfig {
+    pub frequency_hz: u32,
+    pub spreading_factor: u8,
+    /// 125, 250 or 500
+    pub bandwidth_khz: u16,
+    pub tx_preamble: u16,
+    pub rx_preamble: u16,
+    pub tx_power_dbm: i8,
+    pub crc: bool,
+    /// Inverted IQ, as used by gateways for downlinks
+    pub iq_inverted: bool,
+    /// Public LoRaWAN sync word, off for the private sync word
+    pub public: bool,
+}
+
+/// The module's defaults
+impl Default for RfConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 868_000_000,
+            spreading_factor: 7,
+            bandwidth_khz: 125,
+            tx_preamble: 8,
+            rx_preamble: 8,
+            tx_power_dbm: 14,
+            crc: true,
+            iq_inverted: false,
+            public: false,
+        }
+    }
+}
+
+fn on_off(value: &str) -> Result<bool, ()> {
+    match value {
+        "ON" => Ok(true),
+        "OFF" => Ok(false),
+        _ => Err(()),
+    }
+}
+
+impl FromStr for RfConfig {
+    type Err = ();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let value = value.strip_prefix("RFCFG").unwrap_or(value);
+        let mut parts = value.split(',').map(str::trim);
+        let mut field = |prefix: &str| -> Result<&str, ()> {
+            parts.next().and_then(|p| p.strip_prefix(prefix)).ok_or(())
+        };
+        let frequency_hz = field("F:")?.parse().map_err(|_| ())?;
+        let spreading_factor = field("SF")?.parse().map_err(|_| ())?;
+        let bandwidth_khz = field("BW")?.trim_end_matches('K').parse().map_err(|_| ())?;
+        let tx_preamble = field("TXPR:")?.parse().map_err(|_| ())?;
+        let rx_preamble = field("RXPR:")?.parse().map_err(|_| ())?;
+        let tx_power_dbm = field("POW:")?
+            .trim_end_matches("dBm")
+            .parse()
+            .map_err(|_| ())?;
+        let crc = on_off(field("CRC:")?)?;
+        let iq_inverted = on_off(field("IQ:")?)?;
+        let public = on_off(field("NET:")?)?;
+        Ok(Self {
+            frequency_hz,
+            spreading_factor,
+            bandwidth_khz,
+            tx_preamble,
+            rx_preamble,
+            tx_power_dbm,
+            crc,
+            iq_inverted,
+            public,
+        })
+    }
+}
+
+/// Largest LoRa packet TEST mode sends or receives
+pub const MAX_TEST_PACKET_LEN: usize = 255;
+
+/// Packet received in TEST mode (`AT+TEST=RXLRPKT`), with the stats of its reception
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestPacket {
+    pub payload: [u8; MAX_TEST_PACKET_LEN],
+    pub length: usize,
+    pub rssi: i16,
+    pub snr: f32,
+}
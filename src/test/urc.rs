@@ -0,0 +1,71 @@
+use crate::test::types::MAX_TEST_PACKET_LEN;
+use crate::urc::URCMessages;
+use atat::digest::ParseError;
+#[cfg(feature = "debug")]
+use atat::helpers::LossyStr;
+use atat::nom::{bytes, sequence};
+#[cfg(feature = "debug")]
+use defmt::trace;
+
+/// TEST mode URCs
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum TestUrc {
+    /// `TX DONE`, the packet of `TXLRPKT`/`TXLRSTR` has been sent
+    TxDone,
+    /// `LEN:<len>, RSSI:<rssi>, SNR:<snr>`, sent before the payload of a received packet
+    RxStats { length: usize, rssi: i16, snr: f32 },
+    /// `RX "<hex payload>"`
+    Rx {
+        payload: [u8; MAX_TEST_PACKET_LEN],
+        length: usize,
+    },
+}
+
+impl From<TestUrc> for URCMessages {
+    fn from(value: TestUrc) -> Self {
+        Self::Test(value)
+    }
+}
+
+impl TestUrc {
+    pub(crate) fn parse(buf: &[u8]) -> Result<Self, ParseError> {
+        let (val, _) = sequence::tuple((bytes::streaming::tag("+TEST: "),))(buf)?;
+
+        #[cfg(feature = "debug")]
+        {
+            let v = LossyStr(val);
+            trace!("+TEST PARSE: {}", v);
+        }
+        let val = core::str::from_utf8(val).map_err(|_| ParseError::NoMatch)?;
+        match val {
+            x if x.starts_with("TX DONE") => Ok(TestUrc::TxDone),
+            x if x.starts_with("LEN:") => Self::parse_stats(x).ok_or(ParseError::NoMatch),
+            x if x.starts_with("RX \"") => {
+                let hex = x[4..].trim_end_matches('"');
+                if hex.len() % 2 != 0 || hex.len() / 2 > MAX_TEST_PACKET_LEN {
+                    return Err(ParseError::NoMatch);
+                }
+                let mut payload = [0u8; MAX_TEST_PACKET_LEN];
+                for (place, byte) in payload.iter_mut().zip(hex.as_bytes().chunks(2)) {
+                    let byte = core::str::from_utf8(byte).map_err(|_| ParseError::NoMatch)?;
+                    *place = u8::from_str_radix(byte, 16).map_err(|_| ParseError::NoMatch)?;
+                }
+                Ok(TestUrc::Rx {
+                    payload,
+                    length: hex.len() / 2,
+                })
+            }
+            _ => Err(ParseError::NoMatch),
+        }
+    }
+
+    /// Parse `LEN:250, RSSI:-106, SNR:10`
+    fn parse_stats(value: &str) -> Option<Self> {
+        let mut parts = value.split(',').map(str::trim);
+        let length = parts.next()?.strip_prefix("LEN:")?.parse().ok()?;
+        let rssi = parts.next()?.strip_prefix("RSSI:")?.parse().ok()?;
+        let snr = parts.next()?.strip_prefix("SNR:")?.parse().ok()?;
+        Some(TestUrc::RxStats { length, rssi, snr })
+    }
+}
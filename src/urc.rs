@@ -2,17 +2,32 @@
 //!
 //! This is just used internally, but needs to be public for passing [URCMessages] as a generic to
 //! [AtDigester](atat::digest::AtDigester): `AtDigester<URCMessages>`.
+//!
+//! The modem state that the URCs update lives in a [LoraE5State], one per LoRa-E5 module, that is
+//! shared by reference between the [LoraE5Digester](crate::digester::LoraE5Digester) and the client.
 
-use crate::client::asynch::JoinStatus;
-use crate::lora::urc::{JoinUrc, MessageHexSend, MessageReceived};
+use crate::client::JoinStatus;
+use crate::error::LoraE5Error;
+use crate::general::types::PowerState;
+use crate::general::urc::LowPowerUrc;
+use crate::lora::types::BeaconStatus;
+use crate::lora::urc::{BeaconUrc, JoinUrc, MessageHexSend, MessageReceived, Payload};
 use crate::signal::Signal;
+use crate::test::types::TestPacket;
+use crate::test::urc::TestUrc;
 use atat::digest::ParseError;
 use atat::{
     nom::{branch, bytes, combinator, sequence},
     AtatUrc, Parser,
 };
+use core::cell::{Cell, RefCell};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::Channel;
+use embassy_time::Instant;
 
+#[cfg(feature = "debug")]
+use defmt::warn;
 #[cfg(feature = "debug")]
 use embassy_sync::pipe::Pipe;
 
@@ -23,18 +38,32 @@ pub enum URCMessages {
     Unknown,
     /// Join
     Join(JoinUrc),
-    /// Message Hex Sen
+    /// Unconfirmed message hex send (`+MSGHEX`)
     MessageHexSend(MessageHexSend),
+    /// Confirmed message hex send (`+CMSGHEX`)
+    MessageHexConfirmedSend(MessageHexSend),
     /// Message received
     MessageReceived(MessageReceived),
+    /// TEST mode
+    Test(TestUrc),
+    /// Class B beacon
+    Beacon(BeaconUrc),
+    /// Module fell asleep or woke up
+    LowPower(LowPowerUrc),
+    /// `+RESET: ...` banner, the module (re)booted
+    Reset,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct ReceivedMessage {
+    /// Sent to the multicast group rather than to this device
+    pub multicast: bool,
     pub port: u8,
     pub payload: [u8; 243],
     pub length: usize,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct MessageStats {
     pub rxwin: u8,
     pub rssi: i8,
@@ -46,13 +75,354 @@ pub enum SentMessage {
     Success(MessageStats),
 }
 
-pub static LAST_LORA_MESSAGE_RECEIVED: Signal<CriticalSectionRawMutex, ReceivedMessage> =
-    Signal::new();
-pub static LORA_MESSAGE_RECEIVED_COUNT: Signal<CriticalSectionRawMutex, u32> = Signal::new();
-pub static LORA_MESSAGE_RECEIVED_STATS: Signal<CriticalSectionRawMutex, MessageStats> =
-    Signal::new();
-pub static LAST_LORA_MESSAGE_SENT: Signal<CriticalSectionRawMutex, MessageStats> = Signal::new();
-pub static LORA_JOIN_STATUS: Signal<CriticalSectionRawMutex, JoinStatus> = Signal::new();
+/// Outcome of an uplink, as reported by the module up to its `Done`
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SendReport {
+    /// The network server acknowledged the (confirmed) uplink
+    pub ack_received: bool,
+    /// RX window, RSSI and SNR of the downlink frame, if any was received
+    pub stats: Option<MessageStats>,
+    /// The network server has more downlinks pending (`FPENDING`)
+    pub frame_pending: bool,
+    /// Downlink payload that came with the acknowledgement, if any.
+    /// It is also delivered through `receive()`.
+    pub downlink: Option<ReceivedMessage>,
+    /// Link check answer: demodulation margin in dB and number of gateways that received the
+    /// uplink
+    pub link_check: Option<(u8, u8)>,
+}
+
+impl SendReport {
+    pub const fn new() -> Self {
+        Self {
+            ack_received: false,
+            stats: None,
+            frame_pending: false,
+            downlink: None,
+            link_check: None,
+        }
+    }
+}
+
+/// Tracks one `Start` ... `Done` URC sequence of an uplink
+pub(crate) struct SendTracker {
+    report: Mutex<CriticalSectionRawMutex, RefCell<SendReport>>,
+    pub(crate) result: Signal<CriticalSectionRawMutex, Result<SendReport, LoraE5Error>>,
+}
+
+impl SendTracker {
+    const fn new() -> Self {
+        Self {
+            report: Mutex::new(RefCell::new(SendReport::new())),
+            result: Signal::new(),
+        }
+    }
+}
+
+/// Downlinks [LoraE5State] queues by default, until they are received
+pub const DOWNLINK_QUEUE_SIZE: usize = 4;
+
+/// State of a single LoRa-E5 module, as updated by its URCs.
+///
+/// Shared by reference between the [LoraE5Digester](crate::digester::LoraE5Digester) feeding it
+/// and the client reading from it. Can be declared as a `static`:
+///
+/// ```
+/// use seeed_lora_e5_at_commands::urc::LoraE5State;
+///
+/// static LORA_STATE: LoraE5State = LoraE5State::new();
+/// ```
+///
+/// Up to `QUEUE_SIZE` downlinks are kept, each paired with its RX window stats, until the
+/// client receives them. Downlinks arriving while the queue is full are dropped and counted,
+/// see [downlink_overflow_count](Self::downlink_overflow_count). Packets received in TEST mode
+/// are queued the same way.
+pub struct LoraE5State<const QUEUE_SIZE: usize = DOWNLINK_QUEUE_SIZE> {
+    pub(crate) downlinks:
+        Channel<CriticalSectionRawMutex, (ReceivedMessage, MessageStats), QUEUE_SIZE>,
+    pending_downlink: Mutex<CriticalSectionRawMutex, RefCell<Option<ReceivedMessage>>>,
+    downlink_overflows: Mutex<CriticalSectionRawMutex, Cell<u32>>,
+    pub(crate) message_received_count: Signal<CriticalSectionRawMutex, u32>,
+    pub(crate) join_status: Signal<CriticalSectionRawMutex, JoinStatus>,
+    /// Whether the current join attempt already reported its outcome before `+JOIN: Done`
+    join_concluded: Mutex<CriticalSectionRawMutex, Cell<bool>>,
+    pub(crate) unconfirmed_send: SendTracker,
+    pub(crate) confirmed_send: SendTracker,
+    /// `AT+MSG` uplinks, i.e. link checks
+    pub(crate) message_send: SendTracker,
+    pub(crate) test_packets: Channel<CriticalSectionRawMutex, TestPacket, QUEUE_SIZE>,
+    test_rx_stats: Mutex<CriticalSectionRawMutex, Cell<Option<(i16, f32)>>>,
+    pub(crate) test_tx_done: Signal<CriticalSectionRawMutex, ()>,
+    beacon_status: Mutex<CriticalSectionRawMutex, Cell<Option<BeaconStatus>>>,
+    /// Signalled with every Class B beacon state the module reports
+    pub(crate) beacon_status_changed: Signal<CriticalSectionRawMutex, BeaconStatus>,
+    ping_slot_periodicity: Mutex<CriticalSectionRawMutex, Cell<Option<u8>>>,
+    power_state: Mutex<CriticalSectionRawMutex, Cell<PowerState>>,
+    /// Signalled whenever the module falls asleep or wakes up
+    pub(crate) power_state_changed: Signal<CriticalSectionRawMutex, ()>,
+    /// Signalled when the module prints its `+RESET` banner
+    pub(crate) booted: Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl<const QUEUE_SIZE: usize> Default for LoraE5State<QUEUE_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const QUEUE_SIZE: usize> LoraE5State<QUEUE_SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            downlinks: Channel::new(),
+            pending_downlink: Mutex::new(RefCell::new(None)),
+            downlink_overflows: Mutex::new(Cell::new(0)),
+            message_received_count: Signal::new(),
+            join_status: Signal::new(),
+            join_concluded: Mutex::new(Cell::new(false)),
+            unconfirmed_send: SendTracker::new(),
+            confirmed_send: SendTracker::new(),
+            message_send: SendTracker::new(),
+            test_packets: Channel::new(),
+            test_rx_stats: Mutex::new(Cell::new(None)),
+            test_tx_done: Signal::new(),
+            beacon_status: Mutex::new(Cell::new(None)),
+            beacon_status_changed: Signal::new(),
+            ping_slot_periodicity: Mutex::new(Cell::new(None)),
+            power_state: Mutex::new(Cell::new(PowerState::new())),
+            power_state_changed: Signal::new(),
+            booted: Signal::new(),
+        }
+    }
+
+    /// Number of downlinks (or TEST mode packets) dropped because the queue was full
+    pub fn downlink_overflow_count(&self) -> u32 {
+        self.downlink_overflows.lock(|c| c.get())
+    }
+
+    /// Class B beacon state as last reported by the module, if it reported any
+    pub fn beacon_status(&self) -> Option<BeaconStatus> {
+        self.beacon_status.lock(|s| s.get())
+    }
+
+    /// Ping slot periodicity as last reported by the module, if it reported any
+    pub fn ping_slot_periodicity(&self) -> Option<u8> {
+        self.ping_slot_periodicity.lock(|p| p.get())
+    }
+
+    /// Whether the module is asleep, when it last fell asleep and woke up, and how long it slept
+    pub fn power_state(&self) -> PowerState {
+        self.power_state.lock(|p| p.get())
+    }
+
+    /// Record the module falling asleep (`true`) or waking up (`false`)
+    pub(crate) fn power_state_set(&self, asleep: bool) {
+        let now = Instant::now();
+        self.power_state.lock(|p| {
+            let mut state = p.get();
+            if asleep {
+                state.fell_asleep(now);
+            } else {
+                state.woke_up(now);
+            }
+            p.set(state);
+        });
+        self.power_state_changed.signal(());
+    }
+
+    /// Update the state from a parsed URC.
+    /// Called by the [LoraE5Digester](crate::digester::LoraE5Digester) for every URC it matches.
+    pub fn handle_urc(&self, urc: &URCMessages) {
+        match urc {
+            URCMessages::Join(join) => self.handle_join_urc(join),
+            URCMessages::MessageReceived(received) => self.handle_message_urc(received),
+            URCMessages::MessageHexSend(send) => self.handle_send_urc(&self.unconfirmed_send, send),
+            URCMessages::MessageHexConfirmedSend(send) => {
+                self.handle_send_urc(&self.confirmed_send, send)
+            }
+            URCMessages::Test(test) => self.handle_test_urc(test),
+            URCMessages::Beacon(BeaconUrc::Status(status)) => {
+                self.beacon_status.lock(|s| s.set(Some(*status)));
+                self.beacon_status_changed.signal(*status);
+            }
+            URCMessages::Beacon(BeaconUrc::PingSlotPeriodicity(periodicity)) => self
+                .ping_slot_periodicity
+                .lock(|p| p.set(Some(*periodicity))),
+            URCMessages::LowPower(LowPowerUrc::Sleep) => self.power_state_set(true),
+            URCMessages::LowPower(LowPowerUrc::WakeUp) => self.power_state_set(false),
+            URCMessages::Reset => {
+                self.power_state_set(false);
+                self.booted.signal(());
+            }
+            _ => {}
+        }
+    }
+
+    /// A join is about to be sent, its outcome is still to come.
+    /// `+JOIN: Start` is the reply to `AT+JOIN` and does not reach [handle_urc](Self::handle_urc).
+    pub(crate) fn join_started(&self) {
+        self.join_concluded.lock(|c| c.set(false));
+        self.join_status.signal(JoinStatus::Joining);
+    }
+
+    /// `+JOIN` URCs: a join ends in success, `Join failed`, or a bare `Done` when the network
+    /// never answered, the latter two leaving the module not joined
+    fn handle_join_urc(&self, urc: &JoinUrc) {
+        match urc {
+            JoinUrc::Start => self.join_status.signal(JoinStatus::Joining),
+            JoinUrc::Failed => {
+                self.join_concluded.lock(|c| c.set(true));
+                self.join_status.signal(JoinStatus::Failure);
+            }
+            JoinUrc::NetworkJoined | JoinUrc::JoinedAlready | JoinUrc::Success(_, _) => {
+                self.join_concluded.lock(|c| c.set(true));
+                self.join_status.signal(JoinStatus::Success);
+            }
+            JoinUrc::Done if !self.join_concluded.lock(|c| c.replace(true)) => {
+                self.join_status.signal(JoinStatus::Failure);
+            }
+            _ => {}
+        }
+    }
+
+    /// `+MSG` URCs: Class B/C downlinks, and the outcome of `AT+MSG` uplinks
+    fn handle_message_urc(&self, urc: &MessageReceived) {
+        let tracker = &self.message_send;
+        match urc {
+            MessageReceived::Start => {
+                tracker.report.lock(|r| r.take());
+            }
+            MessageReceived::Payload(payload) => {
+                self.downlink_received(payload);
+            }
+            MessageReceived::RxWinRssiSnr(rxwin, rssi, snr) => {
+                let stats = MessageStats {
+                    rxwin: *rxwin,
+                    rssi: *rssi,
+                    snr: *snr,
+                };
+                tracker
+                    .report
+                    .lock(|r| r.borrow_mut().stats = Some(stats.clone()));
+                self.downlink_stats_received(stats);
+            }
+            MessageReceived::FPending => {
+                tracker.report.lock(|r| r.borrow_mut().frame_pending = true)
+            }
+            MessageReceived::Link(margin, gateways) => tracker
+                .report
+                .lock(|r| r.borrow_mut().link_check = Some((*margin, *gateways))),
+            MessageReceived::Done => {
+                let report = tracker.report.lock(|r| r.take());
+                tracker.result.signal(Ok(report));
+            }
+            MessageReceived::Error(e) => {
+                tracker.report.lock(|r| r.take());
+                tracker.result.signal(Err(e.clone()));
+            }
+        }
+    }
+
+    /// TEST mode packets come as their stats followed by their payload
+    fn handle_test_urc(&self, urc: &TestUrc) {
+        match urc {
+            TestUrc::TxDone => self.test_tx_done.signal(()),
+            TestUrc::RxStats { rssi, snr, .. } => {
+                self.test_rx_stats.lock(|s| s.set(Some((*rssi, *snr))))
+            }
+            TestUrc::Rx { payload, length } => {
+                let (rssi, snr) = self.test_rx_stats.lock(|s| s.take()).unwrap_or((0, 0.0));
+                let packet = TestPacket {
+                    payload: *payload,
+                    length: *length,
+                    rssi,
+                    snr,
+                };
+                if self.test_packets.try_send(packet).is_err() {
+                    #[cfg(feature = "debug")]
+                    warn!("TEST packet queue full, dropping packet");
+                    self.downlink_overflows
+                        .lock(|c| c.set(c.get().wrapping_add(1)));
+                }
+            }
+        }
+    }
+
+    /// The payload URC of a downlink, held until its RX window stats arrive
+    fn downlink_received(&self, payload: &Payload) -> ReceivedMessage {
+        let message = ReceivedMessage {
+            multicast: payload.multicast,
+            port: payload.port,
+            payload: payload.payload,
+            length: payload.length,
+        };
+        self.pending_downlink
+            .lock(|m| m.replace(Some(message.clone())));
+
+        let count = match self.message_received_count.try_signaled_value() {
+            Some(v) => {
+                if v == u32::MAX {
+                    0
+                } else {
+                    v + 1
+                }
+            }
+            None => 1,
+        };
+        self.message_received_count.signal(count);
+        message
+    }
+
+    /// The RX window stats URC that follows a downlink payload; queues the pair
+    fn downlink_stats_received(&self, stats: MessageStats) {
+        let Some(message) = self.pending_downlink.lock(|m| m.take()) else {
+            return;
+        };
+        if self.downlinks.try_send((message, stats)).is_err() {
+            #[cfg(feature = "debug")]
+            warn!("Downlink queue full, dropping downlink");
+            self.downlink_overflows
+                .lock(|c| c.set(c.get().wrapping_add(1)));
+        }
+    }
+
+    fn handle_send_urc(&self, tracker: &SendTracker, urc: &MessageHexSend) {
+        match urc {
+            MessageHexSend::Start => {
+                tracker.report.lock(|r| r.take());
+            }
+            MessageHexSend::WaitAck => {}
+            MessageHexSend::AckReceived => {
+                tracker.report.lock(|r| r.borrow_mut().ack_received = true)
+            }
+            MessageHexSend::Pending => tracker.report.lock(|r| r.borrow_mut().frame_pending = true),
+            MessageHexSend::Payload(payload) => {
+                let message = self.downlink_received(payload);
+                tracker
+                    .report
+                    .lock(|r| r.borrow_mut().downlink = Some(message));
+            }
+            MessageHexSend::RxWinRssiSnr(rxwin, rssi, snr) => {
+                let stats = MessageStats {
+                    rxwin: *rxwin,
+                    rssi: *rssi,
+                    snr: *snr,
+                };
+                tracker
+                    .report
+                    .lock(|r| r.borrow_mut().stats = Some(stats.clone()));
+                self.downlink_stats_received(stats);
+            }
+            MessageHexSend::Done => {
+                let report = tracker.report.lock(|r| r.take());
+                tracker.result.signal(Ok(report));
+            }
+            MessageHexSend::Error(e) => {
+                tracker.report.lock(|r| r.take());
+                tracker.result.signal(Err(e.clone()));
+            }
+        }
+    }
+}
 
 #[cfg(feature = "debug")]
 pub static LORA_LATEST_BUF: Pipe<CriticalSectionRawMutex, 50> = Pipe::new();
@@ -65,14 +435,23 @@ impl AtatUrc for URCMessages {
     fn parse(resp: &[u8]) -> Option<Self::Response> {
         match resp {
             b if b.starts_with(b"+JOIN: ") => JoinUrc::parse(resp).ok().map(URCMessages::Join),
-            b if b.starts_with(b"+MSGHEX: ") || b.starts_with(b"+CMSGHEX: ") => {
-                MessageHexSend::parse(resp)
-                    .ok()
-                    .map(URCMessages::MessageHexSend)
-            }
+            b if b.starts_with(b"+MSGHEX: ") => MessageHexSend::parse(resp)
+                .ok()
+                .map(URCMessages::MessageHexSend),
+            b if b.starts_with(b"+CMSGHEX: ") => MessageHexSend::parse(resp)
+                .ok()
+                .map(URCMessages::MessageHexConfirmedSend),
             b if b.starts_with(b"+MSG: ") => MessageReceived::parse(resp)
                 .ok()
                 .map(URCMessages::MessageReceived),
+            b if b.starts_with(b"+TEST: ") => TestUrc::parse(resp).ok().map(URCMessages::Test),
+            b if b.starts_with(b"+BEACON: ") => {
+                BeaconUrc::parse(resp).ok().map(URCMessages::Beacon)
+            }
+            b if b.starts_with(b"+RESET: ") => Some(URCMessages::Reset),
+            b if b.starts_with(b"+LOWPOWER: ") => {
+                LowPowerUrc::parse(resp).ok().map(URCMessages::LowPower)
+            }
             _ => None,
         }
     }
@@ -118,6 +497,58 @@ impl Parser for URCMessages {
                 ))),
                 bytes::streaming::tag("\r\n"),
             )),
+            // Class B beacon state; other `+BEACON` lines are responses
+            sequence::tuple((
+                combinator::success(&b""[..]),
+                combinator::recognize(sequence::tuple((
+                    bytes::streaming::tag("+BEACON: "),
+                    branch::alt((
+                        bytes::streaming::tag("SEARCHING"),
+                        bytes::streaming::tag("LOCKED"),
+                        bytes::streaming::tag("LOST"),
+                        bytes::streaming::tag("FAILED"),
+                        bytes::streaming::tag("PINGSLOT"),
+                    )),
+                    bytes::streaming::take_until("\r\n"),
+                ))),
+                bytes::streaming::tag("\r\n"),
+            )),
+            // Boot banner, after `AT+RESET` or a hard reset
+            sequence::tuple((
+                combinator::success(&b""[..]),
+                combinator::recognize(sequence::tuple((
+                    bytes::streaming::tag("+RESET: "),
+                    bytes::streaming::take_until("\r\n"),
+                ))),
+                bytes::streaming::tag("\r\n"),
+            )),
+            // Sleep and wake up; `AUTOON`/`AUTOOFF` are responses
+            sequence::tuple((
+                combinator::success(&b""[..]),
+                combinator::recognize(sequence::tuple((
+                    bytes::streaming::tag("+LOWPOWER: "),
+                    branch::alt((
+                        bytes::streaming::tag("SLEEP"),
+                        bytes::streaming::tag("WAKEUP"),
+                    )),
+                    bytes::streaming::take_until("\r\n"),
+                ))),
+                bytes::streaming::tag("\r\n"),
+            )),
+            // TEST mode transmit done and received packets; other `+TEST` lines are responses
+            sequence::tuple((
+                combinator::success(&b""[..]),
+                combinator::recognize(sequence::tuple((
+                    bytes::streaming::tag("+TEST: "),
+                    branch::alt((
+                        bytes::streaming::tag("TX DONE"),
+                        bytes::streaming::tag("LEN:"),
+                        bytes::streaming::tag("RX \""),
+                    )),
+                    bytes::streaming::take_until("\r\n"),
+                ))),
+                bytes::streaming::tag("\r\n"),
+            )),
         ))(buf)?;
         Ok((data, head.len() + data.len() + tail.len()))
     }
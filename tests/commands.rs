@@ -0,0 +1,87 @@
+mod common;
+
+use atat::{AtatCmd, InternalError};
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::general::commands::{FactoryReset, FirmwareVersion};
+use seeed_lora_e5_at_commands::lora::commands::{
+    ChannelEnableSet, LoraJoinOtaa, MessageHexConfirmed, MessageHexUnconfirmed,
+};
+
+/// Encode `cmd` into a buffer larger than its `MAX_LEN`, as atat hands it over
+fn encode<Cmd: AtatCmd>(cmd: &Cmd) -> Vec<u8> {
+    let mut buf = [0u8; 64];
+    let len = cmd.write(&mut buf);
+    assert!(len <= Cmd::MAX_LEN);
+    assert!(buf[len..].iter().all(|b| *b == 0));
+    buf[..len].to_vec()
+}
+
+#[test]
+fn factory_reset_is_encoded_and_decoded() {
+    let cmd = FactoryReset {};
+    assert_eq!(encode(&cmd), b"AT+FDEFAULT=Seeed\r\n");
+    assert!(cmd.parse(Ok(b"OK")).unwrap().is_ok());
+    assert_eq!(cmd.parse(Ok(b"ERROR(-1)")), Err(atat::Error::Parse));
+    assert_eq!(
+        cmd.parse(Err(InternalError::Timeout)),
+        Err(atat::Error::Timeout)
+    );
+}
+
+#[test]
+fn firmware_version_is_encoded_and_decoded() {
+    let cmd = FirmwareVersion {};
+    assert_eq!(encode(&cmd), b"AT+VER\r\n");
+    let version = cmd.parse(Ok(b"4.0.11")).unwrap();
+    assert_eq!((version.major, version.minor, version.patch), (4, 0, 11));
+    assert_eq!(cmd.parse(Ok(b"4.0")), Err(atat::Error::Parse));
+    assert_eq!(cmd.parse(Ok(b"4.0.x")), Err(atat::Error::Parse));
+}
+
+#[test]
+fn otaa_join_is_encoded_and_decoded() {
+    let cmd = LoraJoinOtaa {};
+    assert_eq!(encode(&cmd), b"AT+JOIN\r\n");
+    assert_eq!(
+        cmd.parse(Ok(b"Joined already")).unwrap().response.as_str(),
+        "Joined already"
+    );
+    assert_eq!(
+        cmd.parse(Err(InternalError::Timeout)),
+        Err(atat::Error::Timeout)
+    );
+}
+
+#[test]
+fn channel_enable_is_encoded_and_decoded() {
+    let cmd = ChannelEnableSet {
+        index: 2,
+        on: false,
+    };
+    assert_eq!(encode(&cmd), b"AT+CH=2,OFF\r\n");
+    let longest = ChannelEnableSet {
+        index: 255,
+        on: false,
+    };
+    assert_eq!(encode(&longest), b"AT+CH=255,OFF\r\n");
+    let echo = cmd.parse(Ok(b"CH2 OFF")).unwrap();
+    assert_eq!((echo.index, echo.on), (2, false));
+    assert_eq!(cmd.parse(Ok(b"2,OFF")), Err(atat::Error::Parse));
+    let error = cmd
+        .parse(Err(InternalError::Custom(b"ERROR(-1)")))
+        .unwrap_err();
+    assert_eq!(LoraE5Error::from(error), LoraE5Error::InvalidParameter);
+}
+
+#[test]
+fn hex_messages_are_encoded_with_their_length() {
+    let data = [0xCA, 0xFE, 0x00];
+    assert_eq!(
+        encode(&MessageHexUnconfirmed { data: &data }),
+        b"AT+MSGHEX=\"CAFE00\"\r\n"
+    );
+    assert_eq!(
+        encode(&MessageHexConfirmed { data: &data }),
+        b"AT+CMSGHEX=\"CAFE00\"\r\n"
+    );
+}
@@ -0,0 +1,191 @@
+//! Shared harness for running the client against the [LoraE5Simulator].
+#![allow(dead_code)]
+
+use atat::asynch::Client;
+use atat::{AtatIngress, Ingress, ResponseSlot, UrcChannel};
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use embassy_time::Delay;
+use seeed_lora_e5_at_commands::client::{asynch, blocking, ResetPin};
+use seeed_lora_e5_at_commands::digester::LoraE5Digester;
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::simulator::{LoraE5Simulator, SimulatedResetPin};
+use seeed_lora_e5_at_commands::urc::{LoraE5State, URCMessages, DOWNLINK_QUEUE_SIZE};
+use std::sync::Arc;
+use std::task::Wake;
+use std::thread::{self, Thread};
+
+pub const INGRESS_BUF_SIZE: usize = 1024;
+
+pub type TestClient = asynch::SeeedLoraE5Client<'static, LoraE5Simulator, INGRESS_BUF_SIZE>;
+
+pub type TestResetPinClient = asynch::SeeedLoraE5Client<
+    'static,
+    LoraE5Simulator,
+    INGRESS_BUF_SIZE,
+    DOWNLINK_QUEUE_SIZE,
+    ResetPin<SimulatedResetPin, Delay>,
+>;
+
+pub type TestBlockingClient =
+    blocking::SeeedLoraE5Client<'static, LoraE5Simulator, INGRESS_BUF_SIZE>;
+
+/// Discards all defmt output of the crate and atat
+#[cfg(feature = "debug")]
+mod logger {
+    #[defmt::global_logger]
+    struct Logger;
+
+    unsafe impl defmt::Logger for Logger {
+        fn acquire() {}
+        unsafe fn flush() {}
+        unsafe fn release() {}
+        unsafe fn write(_bytes: &[u8]) {}
+    }
+
+    defmt::timestamp!("");
+
+    #[export_name = "_defmt_panic"]
+    fn panic() -> ! {
+        panic!()
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// Poll `background` alongside `future` until `future` completes
+pub async fn run_until<B: Future, F: Future>(background: B, future: F) -> F::Output {
+    let mut background = pin!(background);
+    let mut future = pin!(future);
+    poll_fn(|cx| {
+        let _ = background.as_mut().poll(cx);
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+fn leak<T>(val: T) -> &'static mut T {
+    Box::leak(Box::new(val))
+}
+
+type TestIngress = Ingress<'static, LoraE5Digester<'static>, URCMessages, INGRESS_BUF_SIZE, 1, 1>;
+
+/// Ingress fed from `simulator` and an async AT client writing to it, sharing a fresh state
+fn async_parts(
+    simulator: &LoraE5Simulator,
+) -> (
+    TestIngress,
+    Client<'static, LoraE5Simulator, INGRESS_BUF_SIZE>,
+    &'static LoraE5State,
+) {
+    let state: &'static LoraE5State = leak(LoraE5State::new());
+    let res_slot: &'static ResponseSlot<INGRESS_BUF_SIZE> = leak(ResponseSlot::new());
+    let urc_channel: &'static UrcChannel<URCMessages, 1, 1> = leak(UrcChannel::new());
+    let ingress = Ingress::new(
+        LoraE5Digester::new(state),
+        leak([0u8; INGRESS_BUF_SIZE]),
+        res_slot,
+        urc_channel,
+    );
+    let client = Client::new(
+        simulator.clone(),
+        res_slot,
+        leak([0u8; 1024]),
+        atat::Config::default(),
+    );
+    (ingress, client, state)
+}
+
+/// Build a client on top of `simulator` and run `test` with it while the ingress is fed from the
+/// simulator
+pub fn with_client<F, Fut, R>(simulator: &LoraE5Simulator, test: F) -> R
+where
+    F: FnOnce(Result<TestClient, LoraE5Error>) -> Fut,
+    Fut: Future<Output = R>,
+{
+    let (mut ingress, client, state) = async_parts(simulator);
+    let mut rx = simulator.clone();
+    block_on(run_until(ingress.read_from(&mut rx), async move {
+        test(asynch::SeeedLoraE5Client::new(client, state).await).await
+    }))
+}
+
+/// [with_client], with the client owning the simulated NRST line
+pub fn with_reset_pin_client<F, Fut, R>(simulator: &LoraE5Simulator, test: F) -> R
+where
+    F: FnOnce(Result<TestResetPinClient, LoraE5Error>) -> Fut,
+    Fut: Future<Output = R>,
+{
+    let (mut ingress, client, state) = async_parts(simulator);
+    let reset_pin = ResetPin::new(simulator.reset_pin(), Delay);
+    let mut rx = simulator.clone();
+    block_on(run_until(ingress.read_from(&mut rx), async move {
+        test(asynch::SeeedLoraE5Client::new_with_reset_pin(client, state, reset_pin).await).await
+    }))
+}
+
+/// Build a blocking client on top of `simulator`, with the ingress fed from a reader thread
+pub fn blocking_client(simulator: &LoraE5Simulator) -> Result<TestBlockingClient, LoraE5Error> {
+    let state: &'static LoraE5State = leak(LoraE5State::new());
+    let res_slot: &'static ResponseSlot<INGRESS_BUF_SIZE> = leak(ResponseSlot::new());
+    let urc_channel: &'static UrcChannel<URCMessages, 1, 1> = leak(UrcChannel::new());
+    let mut ingress = Ingress::new(
+        LoraE5Digester::new(state),
+        leak([0u8; INGRESS_BUF_SIZE]),
+        res_slot,
+        urc_channel,
+    );
+    let mut rx = simulator.clone();
+    thread::spawn(move || {
+        let mut buf = [0u8; 64];
+        loop {
+            let len = embedded_io::Read::read(&mut rx, &mut buf).unwrap();
+            let mut written = 0;
+            while written < len {
+                written += ingress.try_write(&buf[written..len]).unwrap_or(0);
+            }
+        }
+    });
+    let client = atat::blocking::Client::new(
+        simulator.clone(),
+        res_slot,
+        leak([0u8; 1024]),
+        atat::Config::default(),
+    );
+    blocking::SeeedLoraE5Client::new(client, state)
+}
+
+/// Shorten RX1/RX2 to 100/200 ms so uplink deadlines expire quickly
+pub async fn shorten_receive_delays(client: &mut TestResetPinClient) {
+    let mut delays = client.receive_delays().await.unwrap();
+    delays.rx1_ms = 100;
+    delays.rx2_ms = 200;
+    client.receive_delays_set(delays).await.unwrap();
+}
+
+/// [shorten_receive_delays] for the blocking client
+pub fn shorten_receive_delays_blocking(client: &mut TestBlockingClient) {
+    let mut delays = client.receive_delays().unwrap();
+    delays.rx1_ms = 100;
+    delays.rx2_ms = 200;
+    client.receive_delays_set(delays).unwrap();
+}
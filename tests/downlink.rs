@@ -0,0 +1,134 @@
+//! Downlinks, their routing and multicast groups against the simulator
+
+mod common;
+
+use common::with_client;
+use seeed_lora_e5_at_commands::lora::router::DownlinkRouter;
+use seeed_lora_e5_at_commands::simulator::{LoraE5Simulator, SimulatedDownlink};
+use seeed_lora_e5_at_commands::urc::MessageStats;
+
+#[test]
+fn class_c_downlink_is_received() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    let modem = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        modem.push_downlink(SimulatedDownlink::new(10, b"hello"));
+        let (message, stats) = client.receive().await.unwrap();
+        assert_eq!(message.port, 10);
+        assert_eq!(&message.payload[..message.length], b"hello");
+        assert_eq!(stats.rxwin, 1);
+        assert_eq!(stats.rssi, -106);
+        assert_eq!(client.downlink_message_count().await, Ok(1));
+    });
+}
+
+#[test]
+fn back_to_back_downlinks_are_queued() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    let modem = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        for port in 1..=5 {
+            let mut downlink = SimulatedDownlink::new(port, b"x");
+            downlink.rssi = -100 - port as i8;
+            modem.push_downlink(downlink);
+        }
+        // URCs are digested in order, so they have all been handled once this returns
+        client.verify_com_is_working().await.unwrap();
+        for port in 1..=4 {
+            let (message, stats) = client.try_receive().unwrap();
+            assert_eq!(message.port, port);
+            assert_eq!(stats.rssi, -100 - port as i8);
+        }
+        assert_eq!(client.try_receive(), None);
+        assert_eq!(client.state().downlink_overflow_count(), 1);
+    });
+}
+
+#[test]
+fn send_report_carries_downlink() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    simulator.queue_downlink(SimulatedDownlink::new(3, b"pong"));
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        client.confirm_send_set(true).await.unwrap();
+        let report = client.send(1, 12, b"ping").await.unwrap();
+        let downlink = report.downlink.unwrap();
+        assert_eq!(downlink.port, 3);
+        assert_eq!(&downlink.payload[..downlink.length], b"pong");
+        let (message, _) = client.receive().await.unwrap();
+        assert_eq!(message, downlink);
+    });
+}
+
+#[test]
+fn downlinks_are_routed_by_port() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    let modem = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let mut commands = Vec::new();
+        let mut ota = Vec::new();
+        let mut unknown = Vec::new();
+        let mut on_command =
+            |_port: u8, payload: &[u8], _stats: &MessageStats| commands.push(payload.to_vec());
+        let mut on_ota =
+            |port: u8, _payload: &[u8], stats: &MessageStats| ota.push((port, stats.rssi));
+        let mut on_unknown = |port: u8, _payload: &[u8], _stats: &MessageStats| unknown.push(port);
+        let mut router: DownlinkRouter<'_, 2> = DownlinkRouter::new();
+        assert!(router.route(1, &mut on_command).is_ok());
+        assert!(router.route_range(20..=29, &mut on_ota).is_ok());
+        router.default_route(&mut on_unknown);
+
+        for port in [1, 21, 42] {
+            modem.push_downlink(SimulatedDownlink::new(port, b"go"));
+            assert_eq!(client.receive_and_route(&mut router).await, Ok(true));
+        }
+        drop(router);
+        assert_eq!(commands, [b"go".to_vec()]);
+        assert_eq!(ota, [(21, -106)]);
+        assert_eq!(unknown, [42]);
+    });
+}
+
+#[test]
+fn multicast_downlinks_are_marked() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(client.multicast().await, Ok(None));
+        assert_eq!(
+            client
+                .multicast_set(0x0123_4567, 0x2B7E_1516_28AE_D2A6_ABF7_1588_09CF_4F3C, 1)
+                .await,
+            Ok(0x0123_4567)
+        );
+        assert_eq!(client.multicast().await, Ok(Some(0x0123_4567)));
+
+        let mut downlink = SimulatedDownlink::new(200, b"fw");
+        downlink.multicast = true;
+        sim.push_downlink(downlink);
+        sim.push_downlink(SimulatedDownlink::new(10, b"me"));
+        let (group, _) = client.receive().await.unwrap();
+        assert!(group.multicast);
+        assert_eq!(
+            (group.port, &group.payload[..group.length]),
+            (200, &b"fw"[..])
+        );
+        let (unicast, _) = client.receive().await.unwrap();
+        assert!(!unicast.multicast);
+
+        client.multicast_disable().await.unwrap();
+        assert_eq!(client.multicast().await, Ok(None));
+    });
+    assert!(simulator.commands().contains(
+        &"AT+LW=MC,ON,\"01234567\",\"2B7E151628AED2A6ABF7158809CF4F3C\",\"00000000000000000000000000000001\""
+            .into()
+    ));
+}
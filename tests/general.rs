@@ -0,0 +1,146 @@
+//! Module management against the simulator: startup, resets and low power
+
+mod common;
+
+use common::{blocking_client, with_client, with_reset_pin_client};
+use embassy_time::{Duration, Timer};
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::lora::types::LoraJoinMode;
+use seeed_lora_e5_at_commands::simulator::LoraE5Simulator;
+
+#[test]
+fn client_starts_and_reads_ids() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let version = client.version().await.unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (4, 0, 11));
+        assert_eq!(
+            client.dev_eui_set(0x68419fa0f7e74b0d).await,
+            Ok(0x68419fa0f7e74b0d)
+        );
+        assert_eq!(client.dev_eui().await, Ok(0x68419fa0f7e74b0d));
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+    });
+}
+
+#[test]
+fn factory_reset_restores_defaults() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let default_dev_eui = client.dev_eui().await.unwrap();
+        client.dev_eui_set(0x68419fa0f7e74b0d).await.unwrap();
+        assert_eq!(client.factory_reset().await, Ok(()));
+        assert_eq!(client.dev_eui().await, Ok(default_dev_eui));
+    });
+    assert!(simulator
+        .commands()
+        .iter()
+        .any(|command| command == "AT+FDEFAULT=Seeed"));
+}
+
+#[test]
+fn hard_reset_recovers_wedged_module() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    simulator.wedge();
+    let sim = simulator.clone();
+    with_reset_pin_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert!(!sim.is_joined());
+        assert_eq!(client.verify_com_is_working().await, Ok(true));
+
+        sim.wedge();
+        assert_eq!(
+            client.verify_com_is_working().await,
+            Err(LoraE5Error::At(atat::Error::Timeout))
+        );
+        client.hard_reset().await.unwrap();
+        assert_eq!(client.verify_com_is_working().await, Ok(true));
+        client.reset().await.unwrap();
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+    });
+    let commands = simulator.commands();
+    assert_eq!(commands.iter().filter(|c| *c == "AT+RESET").count(), 1);
+    assert!(!commands.iter().any(|c| c.starts_with("AT+FDEFAULT")));
+}
+
+#[test]
+fn auto_low_power_wakes_module_before_commands() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        client.sleep_until_woken().await.unwrap();
+        assert!(sim.is_asleep());
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+
+        assert_eq!(client.auto_low_power_set(true).await, Ok(true));
+        assert!(client.auto_low_power());
+        assert!(sim.is_asleep());
+        assert!(client.power_state().asleep);
+        assert_eq!(client.dev_eui().await, Ok(0x2CF7F12024200001));
+        assert!(sim.is_asleep());
+        // Sleeps after commands go unreported and are not recorded
+        assert!(!client.power_state().asleep);
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+        assert!(sim.is_asleep());
+        assert_eq!(client.auto_low_power_set(false).await, Ok(false));
+        assert!(!sim.is_asleep());
+
+        client.sleep_for(1).await.unwrap();
+        Timer::after(Duration::from_millis(5)).await;
+        assert!(!sim.is_asleep());
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+    });
+    let low_power_commands = simulator
+        .commands()
+        .iter()
+        .filter(|c| c.starts_with("AT+LOWPOWER"))
+        .count();
+    assert_eq!(low_power_commands, 4);
+}
+
+#[test]
+fn blocking_auto_low_power_wakes_module_before_commands() {
+    let simulator = LoraE5Simulator::new();
+    let mut client = blocking_client(&simulator).unwrap();
+    assert_eq!(client.auto_low_power_set(true), Ok(true));
+    assert!(client.power_state().asleep);
+    assert_eq!(client.join_mode(), Ok(LoraJoinMode::Otaa));
+    assert!(!client.power_state().asleep);
+    assert_eq!(client.join_mode(), Ok(LoraJoinMode::Otaa));
+    assert!(simulator.is_asleep());
+    assert_eq!(client.auto_low_power_set(false), Ok(false));
+    assert!(!simulator.is_asleep());
+}
+
+#[test]
+fn power_state_tracks_sleep() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert!(!client.power_state().asleep);
+        assert_eq!(client.power_state().total_sleep, Duration::from_ticks(0));
+
+        client.sleep_for(20).await.unwrap();
+        let state = client.power_state();
+        assert!(state.asleep);
+        assert!(state.fell_asleep_at.is_some());
+        Timer::after(Duration::from_millis(30)).await;
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+        let state = client.power_state();
+        assert!(!state.asleep);
+        assert!(state.woke_at.is_some());
+        let first = state.last_sleep.unwrap();
+        assert!(first >= Duration::from_millis(20));
+
+        client.sleep_until_woken().await.unwrap();
+        assert!(client.power_state().asleep);
+        client.wake_up().await.unwrap();
+        let state = client.power_state();
+        assert!(!state.asleep);
+        assert_eq!(state.total_sleep, first + state.last_sleep.unwrap());
+    });
+}
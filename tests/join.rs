@@ -0,0 +1,82 @@
+//! OTAA joins and ABP activation against the simulator
+
+mod common;
+
+use common::{blocking_client, with_client};
+use seeed_lora_e5_at_commands::client::asynch::JoinStatus;
+use seeed_lora_e5_at_commands::lora::types::LoraJoinMode;
+use seeed_lora_e5_at_commands::simulator::LoraE5Simulator;
+
+#[test]
+fn otaa_join_succeeds() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let status = client.lora_join_otaa_and_wait_for_result().await.unwrap();
+        assert!(matches!(status, JoinStatus::Success));
+    });
+    assert!(simulator.is_joined());
+}
+
+#[test]
+fn otaa_join_reject_is_reported() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_join_accept(false);
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let status = client.lora_join_otaa_and_wait_for_result().await.unwrap();
+        assert!(matches!(status, JoinStatus::Failure));
+    });
+    assert!(!simulator.is_joined());
+    let mut client = blocking_client(&simulator).unwrap();
+    let status = client.lora_join_otaa_and_wait_for_result().unwrap();
+    assert!(matches!(status, JoinStatus::Failure));
+}
+
+#[test]
+fn unanswered_join_after_failed_join_is_reported() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_join_accept(false);
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let status = client.lora_join_otaa_and_wait_for_result().await.unwrap();
+        assert!(matches!(status, JoinStatus::Failure));
+        sim.set_join_unanswered(true);
+        let status = client.lora_join_otaa_and_wait_for_result().await.unwrap();
+        assert!(matches!(status, JoinStatus::Failure));
+    });
+    let mut client = blocking_client(&simulator).unwrap();
+    let status = client.lora_join_otaa_and_wait_for_result().unwrap();
+    assert!(matches!(status, JoinStatus::Failure));
+}
+
+#[test]
+fn abp_activation_provisions_session() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let nwk_s_key = 0x2B7E151628AED2A6ABF7158809CF4F3C;
+        let app_s_key = 0x000102030405060708090A0B0C0D0E0F;
+        client
+            .abp_activate(0x26011BDC, nwk_s_key, app_s_key)
+            .await
+            .unwrap();
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Abp));
+        assert_eq!(client.dev_addr().await, Ok(0x26011BDC));
+        assert!(client.send_unconfirmed(1, b"abp", 0).await.is_ok());
+    });
+    let commands = simulator.commands();
+    assert!(commands
+        .iter()
+        .any(|command| command == "AT+KEY=\"APPSKEY\",\"000102030405060708090A0B0C0D0E0F\""));
+}
+
+#[test]
+fn blocking_client_joins() {
+    let simulator = LoraE5Simulator::new();
+    let mut client = blocking_client(&simulator).unwrap();
+    assert_eq!(client.join_mode(), Ok(LoraJoinMode::Otaa));
+    let status = client.lora_join_otaa_and_wait_for_result().unwrap();
+    assert!(matches!(status, JoinStatus::Success));
+}
@@ -0,0 +1,284 @@
+//! LoRaWAN settings against the simulator, and module errors on setters
+
+mod common;
+
+use common::with_client;
+use embassy_time::{with_timeout, Duration};
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::lora::types::{
+    BeaconChannel, BeaconStatus, DataRate, DataRateInfo, LoraChannel, LoraClass, LoraJoinMode,
+    LoraRegion, LoraVersion, Rx1Channel, Rx2Window,
+};
+use seeed_lora_e5_at_commands::simulator::LoraE5Simulator;
+use std::thread;
+
+#[test]
+fn data_rate_is_parsed() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let expected = DataRateInfo {
+            region: LoraRegion::Eu868,
+            dr: 3,
+            spreading_factor: Some(9),
+            bandwidth_khz: Some(125),
+            fsk: false,
+        };
+        assert_eq!(client.dr_set(3).await, Ok(expected.clone()));
+        assert_eq!(client.dr_set(16).await, Err(LoraE5Error::InvalidParameter));
+        assert_eq!(client.dr().await, Ok(expected));
+        assert_eq!(client.lora_region().await, Ok(LoraRegion::Eu868));
+    });
+    assert_eq!(
+        "EU868 DR7 FSK".parse(),
+        Ok(DataRateInfo {
+            region: LoraRegion::Eu868,
+            dr: 7,
+            spreading_factor: None,
+            bandwidth_khz: None,
+            fsk: true,
+        })
+    );
+}
+
+#[test]
+fn channel_plan_is_managed() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let added = LoraChannel {
+            index: 3,
+            frequency_hz: 867_100_000,
+            dr_min: 0,
+            dr_max: 5,
+        };
+        assert_eq!(
+            client.channel_set(3, 867_100_000, 0, 5).await,
+            Ok(added.clone())
+        );
+        assert_eq!(client.channel_enable_set(1, false).await, Ok(false));
+        let channels = client.channels().await.unwrap();
+        let indices: Vec<u8> = channels.iter().map(|c| c.index).collect();
+        assert_eq!(indices, [0, 2, 3]);
+        assert_eq!(channels[2], added);
+
+        assert_eq!(client.channel_mask_set(1).await, Ok(0..=7));
+        assert_eq!(
+            client.channel_mask_set(9).await,
+            Err(LoraE5Error::InvalidParameter)
+        );
+    });
+    assert!(simulator
+        .commands()
+        .iter()
+        .any(|command| command == "AT+CH=3,867.100000,0,5"));
+}
+
+#[test]
+fn rx_windows_are_configured() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let dr3 = DataRate::try_from(3).unwrap();
+        let rx2 = Rx2Window {
+            frequency_hz: 869_525_000,
+            data_rate: dr3,
+        };
+        assert_eq!(
+            client.rx2_window_set(869_525_000, dr3).await,
+            Ok(rx2.clone())
+        );
+        assert_eq!(client.rx2_window().await, Ok(rx2));
+        assert_eq!("4295.000000,DR3".parse::<Rx2Window>(), Err(()));
+        assert_eq!("5000,DR3".parse::<Rx2Window>(), Err(()));
+
+        let rx1 = Rx1Channel {
+            index: 1,
+            frequency_hz: 867_300_000,
+        };
+        assert_eq!(
+            client.rx1_channel_set(1, 867_300_000).await,
+            Ok(rx1.clone())
+        );
+        let channels = client.rx1_channels().await.unwrap();
+        assert_eq!(channels.len(), 3);
+        assert_eq!(channels[1], rx1);
+    });
+    assert!(DataRate::try_from(16).is_err());
+}
+
+#[test]
+fn receive_delays_round_trip() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let mut delays = client.receive_delays().await.unwrap();
+        assert_eq!(delays.rx1_ms, 1000);
+        delays.rx1_ms = 5000;
+        delays.rx2_ms = 6000;
+        assert_eq!(
+            client.receive_delays_set(delays.clone()).await,
+            Ok(delays.clone())
+        );
+        assert_eq!(client.receive_delays().await, Ok(delays.clone()));
+
+        delays.rx2_ms = 4000;
+        assert_eq!(
+            client.receive_delays_set(delays).await,
+            Err(LoraE5Error::InvalidParameter)
+        );
+    });
+}
+
+#[test]
+fn lorawan_settings_are_managed() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(client.duty_cycle().await, Ok(true));
+        assert_eq!(client.duty_cycle_set(false).await, Ok(false));
+        assert_eq!(client.duty_cycle().await, Ok(false));
+        assert_eq!(client.join_duty_cycle_set(false).await, Ok(false));
+        assert_eq!(client.public_network_set(false).await, Ok(false));
+        assert_eq!(client.public_network().await, Ok(false));
+
+        assert_eq!(client.lorawan_version().await, Ok(LoraVersion::V102B));
+        assert_eq!(
+            client.lorawan_version_set(LoraVersion::V103).await,
+            Ok(LoraVersion::V103)
+        );
+
+        client.dr_set(3).await.unwrap();
+        assert_eq!(
+            client.current_data_rate().await,
+            Ok(DataRate::try_from(3).unwrap())
+        );
+
+        assert_eq!(client.battery_level().await, Ok(255));
+        assert_eq!(client.battery_level_set(0).await, Ok(0));
+
+        assert_eq!(
+            client.uplink_downlink_counter_set(120, 7).await,
+            Ok((120, 7))
+        );
+        assert_eq!(client.uplink_frame_count().await, Ok(120));
+        assert_eq!(client.downlink_frame_count().await, Ok(7));
+    });
+}
+
+#[test]
+fn class_b_beacon_is_tracked() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let dr2 = DataRate::try_from(2).unwrap();
+        assert_eq!(
+            client.beacon_set(869_525_000, dr2).await,
+            Ok(BeaconChannel {
+                frequency_hz: 869_525_000,
+                data_rate: dr2
+            })
+        );
+        assert_eq!(client.ping_slot_periodicity_set(5).await, Ok(5));
+        assert_eq!(
+            client.ping_slot_periodicity_set(8).await,
+            Err(LoraE5Error::InvalidParameter)
+        );
+        assert_eq!(client.beacon_status().await, Ok(BeaconStatus::Idle));
+
+        client.lora_class_set(LoraClass::ClassB).await.unwrap();
+        assert_eq!(client.beacon_status().await, Ok(BeaconStatus::Locked));
+        assert_eq!(client.state().beacon_status(), Some(BeaconStatus::Locked));
+        assert_eq!(client.state().ping_slot_periodicity(), Some(5));
+        // The LOCKED report came before the wait, the wait is for the next change
+        assert!(
+            with_timeout(Duration::from_millis(100), client.beacon_status_wait())
+                .await
+                .is_err()
+        );
+        assert_eq!(client.state().beacon_status(), Some(BeaconStatus::Locked));
+
+        let lose = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            sim.lose_beacon();
+        });
+        assert_eq!(client.beacon_status_wait().await, Ok(BeaconStatus::Lost));
+        assert_eq!(client.state().beacon_status(), Some(BeaconStatus::Lost));
+        lose.join().unwrap();
+    });
+}
+
+#[test]
+fn injected_error_is_typed() {
+    let simulator = LoraE5Simulator::new();
+    simulator.inject_error("AT+MODE", LoraE5Error::UnavailableInCurrentMode);
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(
+            client.join_mode_set(LoraJoinMode::Abp).await,
+            Err(LoraE5Error::UnavailableInCurrentMode)
+        );
+        assert_eq!(
+            client.join_mode_set(LoraJoinMode::Abp).await,
+            Ok(LoraJoinMode::Abp)
+        );
+    });
+}
+
+#[test]
+fn setters_report_module_errors() {
+    use LoraE5Error::*;
+    let simulator = LoraE5Simulator::new();
+    let injected = [
+        ("AT+DR=", InvalidParameter),
+        ("AT+CH=1,", UnavailableInCurrentMode),
+        ("AT+CH=3,", InvalidParameter),
+        ("AT+CH=NUM", WrongFormat),
+        ("AT+RXWIN1", InvalidParameter),
+        ("AT+RXWIN2", InvalidParameter),
+        ("AT+DELAY=RX1", InvalidParameter),
+        ("AT+CLASS", UnavailableInCurrentMode),
+        ("AT+ADR", WrongFormat),
+        ("AT+LW=VER", InvalidParameter),
+        ("AT+LW=DC", InvalidParameter),
+        ("AT+LW=NET", InvalidParameter),
+        ("AT+LW=BAT", InvalidParameter),
+        ("AT+LW=ULDL", InvalidParameter),
+        ("AT+ID", InvalidParameter),
+        ("AT+KEY", WrongFormat),
+    ];
+    for (command, error) in &injected {
+        simulator.inject_error(command, error.clone());
+    }
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        let dr3 = DataRate::try_from(3).unwrap();
+        let delays = client.receive_delays().await.unwrap();
+        let results = [
+            client.dr_set(3).await.map(drop),
+            client.channel_enable_set(1, false).await.map(drop),
+            client.channel_set(3, 867_100_000, 0, 5).await.map(drop),
+            client.channel_mask_set(2).await.map(drop),
+            client.rx1_channel_set(1, 867_300_000).await.map(drop),
+            client.rx2_window_set(869_525_000, dr3).await.map(drop),
+            client.receive_delays_set(delays).await.map(drop),
+            client.lora_class_set(LoraClass::ClassC).await.map(drop),
+            client.adr_set(true).await.map(drop),
+            client
+                .lorawan_version_set(LoraVersion::V103)
+                .await
+                .map(drop),
+            client.duty_cycle_set(false).await.map(drop),
+            client.public_network_set(false).await.map(drop),
+            client.battery_level_set(0).await.map(drop),
+            client.uplink_downlink_counter_set(1, 1).await.map(drop),
+            client.dev_eui_set(0x0102_0304_0506_0708).await.map(drop),
+            client.app_key_set(1).await.map(drop),
+        ];
+        for ((command, error), result) in injected.iter().zip(results) {
+            assert_eq!(result, Err(error.clone()), "{}", command);
+        }
+    });
+}
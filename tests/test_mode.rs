@@ -0,0 +1,91 @@
+//! TEST mode and RF tests against the simulator
+
+mod common;
+
+use common::with_client;
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::lora::types::LoraJoinMode;
+use seeed_lora_e5_at_commands::simulator::LoraE5Simulator;
+use seeed_lora_e5_at_commands::test::types::RfConfig;
+
+#[test]
+fn test_mode_sends_and_receives_packets() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(
+            client.test_rf_config().await,
+            Err(LoraE5Error::UnavailableInCurrentMode)
+        );
+        client.join_mode_set(LoraJoinMode::Test).await.unwrap();
+
+        let config = RfConfig {
+            frequency_hz: 868_100_000,
+            spreading_factor: 12,
+            tx_power_dbm: 20,
+            ..RfConfig::default()
+        };
+        assert_eq!(
+            client.test_rf_config_set(config.clone()).await,
+            Ok(config.clone())
+        );
+        assert_eq!(client.test_rf_config().await, Ok(config));
+
+        client.test_send(&[0x01, 0xAB, 0x00]).await.unwrap();
+        client.test_send_str("hello").await.unwrap();
+        for text in ["say \"hi\"", "two\r\nlines"] {
+            assert_eq!(
+                client.test_send_str(text).await,
+                Err(LoraE5Error::InvalidParameter)
+            );
+        }
+
+        client.test_receive_start().await.unwrap();
+        sim.push_test_packet(&[0xCA, 0xFE], -98, 7);
+        let packet = client.test_receive().await.unwrap();
+        assert_eq!(&packet.payload[..packet.length], &[0xCA, 0xFE]);
+        assert_eq!((packet.rssi, packet.snr), (-98, 7.0));
+        client.test_stop().await.unwrap();
+    });
+    let commands = simulator.commands();
+    assert!(commands.contains(&"AT+TEST=RFCFG,868.100000,SF12,125,8,8,20,ON,OFF,OFF".into()));
+    assert!(commands.contains(&"AT+TEST=TXLRPKT,\"01AB00\"".into()));
+    assert!(commands.contains(&"AT+TEST=TXLRSTR,\"hello\"".into()));
+    let texts = commands.iter().filter(|c| c.starts_with("AT+TEST=TXLRSTR"));
+    assert_eq!(texts.count(), 1);
+}
+
+#[test]
+fn rf_test_restores_previous_mode() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(
+            client.rf_test_config().await,
+            Err(LoraE5Error::UnavailableInCurrentMode)
+        );
+        let config = client.rf_test_cw(915_000_000, 20).await.unwrap();
+        assert_eq!(
+            (config.frequency_hz, config.tx_power_dbm),
+            (915_000_000, 20)
+        );
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Test));
+        assert_eq!(client.rf_test_config().await, Ok(config));
+
+        client.rf_test_clora(868_300_000, 10).await.unwrap();
+        sim.inject_error("AT+MODE", LoraE5Error::UnavailableInCurrentMode);
+        assert_eq!(
+            client.rf_test_stop().await,
+            Err(LoraE5Error::UnavailableInCurrentMode)
+        );
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Test));
+        client.rf_test_stop().await.unwrap();
+        assert_eq!(client.join_mode().await, Ok(LoraJoinMode::Otaa));
+    });
+    let commands = simulator.commands();
+    assert!(commands.contains(&"AT+TEST=TXCW".into()));
+    assert!(commands.contains(&"AT+TEST=TXCLORA".into()));
+    assert_eq!(commands.last().map(String::as_str), Some("AT+MODE"));
+}
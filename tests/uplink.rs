@@ -0,0 +1,226 @@
+//! Uplinks and link checks against the simulator
+
+mod common;
+
+use common::{
+    blocking_client, shorten_receive_delays, shorten_receive_delays_blocking, with_client,
+    with_reset_pin_client,
+};
+use seeed_lora_e5_at_commands::error::LoraE5Error;
+use seeed_lora_e5_at_commands::lora::types::{LinkCheckResult, MAX_UPLINK_LEN};
+use seeed_lora_e5_at_commands::simulator::{LoraE5Simulator, SimulatedDownlink};
+
+#[test]
+fn unconfirmed_send_uses_msghex() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    simulator.queue_downlink(SimulatedDownlink::new(2, b"down"));
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        client.confirm_send_set(false).await.unwrap();
+        let report = client.send(1, 12, b"up").await.unwrap();
+        assert!(!report.ack_received);
+        let downlink = report.downlink.unwrap();
+        assert_eq!(&downlink.payload[..downlink.length], b"down");
+    });
+    let commands = simulator.commands();
+    assert!(commands
+        .iter()
+        .any(|command| command == "AT+MSGHEX=\"7570\""));
+    assert!(!commands
+        .iter()
+        .any(|command| command.starts_with("AT+CMSGHEX")));
+}
+
+#[test]
+fn confirmed_send_reports_ack_and_stats() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        client.confirm_send_set(true).await.unwrap();
+        let report = client.send(1, 12, b"hi").await.unwrap();
+        assert!(report.ack_received);
+        assert!(!report.frame_pending);
+        assert_eq!(report.downlink, None);
+        let stats = report.stats.unwrap();
+        assert_eq!((stats.rxwin, stats.rssi, stats.snr), (1, -106, 4.0));
+    });
+    assert!(simulator
+        .commands()
+        .iter()
+        .any(|command| command == "AT+CMSGHEX=\"6869\""));
+}
+
+#[test]
+fn per_message_confirmation_ignores_default() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(client.confirm_send().await, Ok(false));
+        let report = client.send_confirmed(5, b"c", 2).await.unwrap();
+        assert!(report.ack_received);
+        let report = client.send_unconfirmed(5, b"u", 1).await.unwrap();
+        assert!(!report.ack_received);
+    });
+    let commands = simulator.commands();
+    let sends: Vec<_> = commands
+        .iter()
+        .filter(|command| command.contains("MSGHEX") || command.starts_with("AT+RE"))
+        .collect();
+    assert_eq!(
+        sends,
+        [
+            "AT+RETRY=2",
+            "AT+CMSGHEX=\"63\"",
+            "AT+REPT=1",
+            "AT+MSGHEX=\"75\""
+        ]
+    );
+}
+
+#[test]
+fn uplink_keeps_trailing_zeros_and_checks_length() {
+    let simulator = LoraE5Simulator::new();
+    simulator.set_joined(true);
+    simulator.set_max_payload_len(4);
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert!(client
+            .send_unconfirmed(1, &[0x01, 0x00, 0x00], 0)
+            .await
+            .is_ok());
+        assert!(client.send_confirmed(1, &[0x00; 4], 0).await.is_ok());
+        assert_eq!(
+            client.send_unconfirmed(1, &[0x00; 5], 0).await,
+            Err(LoraE5Error::LengthError)
+        );
+        assert_eq!(
+            client
+                .send_unconfirmed(1, &[0x00; MAX_UPLINK_LEN + 1], 0)
+                .await,
+            Err(LoraE5Error::LengthError)
+        );
+        client.dr_set(3).await.unwrap();
+        assert!(client.send_unconfirmed(1, &[0x02], 0).await.is_ok());
+    });
+    let commands = simulator.commands();
+    let sends: Vec<_> = commands
+        .iter()
+        .filter(|command| command.contains("MSGHEX"))
+        .collect();
+    assert_eq!(
+        sends,
+        [
+            "AT+MSGHEX=\"010000\"",
+            "AT+CMSGHEX=\"00000000\"",
+            "AT+MSGHEX=\"02\""
+        ]
+    );
+    let len_reads = commands.iter().filter(|c| *c == "AT+LW=LEN").count();
+    assert_eq!(len_reads, 2);
+}
+
+#[test]
+fn send_before_join_fails() {
+    let simulator = LoraE5Simulator::new();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        client.confirm_send_set(true).await.unwrap();
+        assert_eq!(client.send(1, 12, b"hi").await, Err(LoraE5Error::NotJoined));
+    });
+}
+
+#[test]
+fn uplink_without_done_times_out() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_reset_pin_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        sim.set_joined(true);
+        shorten_receive_delays(&mut client).await;
+
+        sim.wedge_during_uplink();
+        assert_eq!(
+            client.send_unconfirmed(1, b"lost", 0).await,
+            Err(LoraE5Error::Timeout)
+        );
+        client.hard_reset().await.unwrap();
+        assert_eq!(client.verify_com_is_working().await, Ok(true));
+    });
+}
+
+#[test]
+fn link_check_reports_margin_and_gateways() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        assert_eq!(client.link_check().await, Err(LoraE5Error::NotJoined));
+        sim.set_joined(true);
+        assert_eq!(
+            client.link_check().await,
+            Ok(LinkCheckResult {
+                margin_db: 20,
+                gateway_count: 1,
+                rssi: -98,
+                snr: 7.5
+            })
+        );
+
+        sim.set_link_check_answer(None);
+        assert_eq!(
+            client.link_check().await,
+            Err(LoraE5Error::NoLinkCheckAnswer)
+        );
+
+        let mut misses = 0;
+        assert_eq!(client.link_watchdog_tick(&mut misses, 2).await, Ok(None));
+        sim.set_link_check_answer(Some((12, 2)));
+        assert!(matches!(
+            client.link_watchdog_tick(&mut misses, 2).await,
+            Ok(Some(LinkCheckResult {
+                gateway_count: 2,
+                ..
+            }))
+        ));
+        assert_eq!(misses, 0);
+        sim.set_link_check_answer(None);
+        assert_eq!(client.link_watchdog_tick(&mut misses, 2).await, Ok(None));
+        assert_eq!(
+            client.link_watchdog_tick(&mut misses, 2).await,
+            Err(LoraE5Error::NoLinkCheckAnswer)
+        );
+        assert_eq!(misses, 2);
+    });
+    let link_checks = simulator
+        .commands()
+        .iter()
+        .filter(|c| *c == "AT+MSG")
+        .count();
+    assert_eq!(link_checks, 7);
+}
+
+#[test]
+fn link_check_times_out_when_module_wedges() {
+    let simulator = LoraE5Simulator::new();
+    let sim = simulator.clone();
+    with_reset_pin_client(&simulator, |client| async move {
+        let mut client = client.unwrap();
+        sim.set_joined(true);
+        shorten_receive_delays(&mut client).await;
+
+        sim.wedge_during_uplink();
+        assert_eq!(client.link_check().await, Err(LoraE5Error::Timeout));
+        client.hard_reset().await.unwrap();
+        assert_eq!(client.verify_com_is_working().await, Ok(true));
+    });
+
+    let simulator = LoraE5Simulator::new();
+    let mut client = blocking_client(&simulator).unwrap();
+    simulator.set_joined(true);
+    shorten_receive_delays_blocking(&mut client);
+    simulator.wedge_during_uplink();
+    assert_eq!(client.link_check(), Err(LoraE5Error::Timeout));
+}